@@ -0,0 +1,66 @@
+use {
+	crate::world::tile::TilePos,
+	crow::Texture,
+	serde::Deserialize,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Looping {
+	Loop,
+	PingPong,
+}
+
+pub struct AnimationFrame {
+	pub texture: Texture,
+	pub duration: usize, // Measured in frames
+}
+
+pub struct Animation {
+	pub frames: Vec<AnimationFrame>,
+	pub looping: Looping,
+}
+impl Animation {
+	pub fn new(frames: Vec<AnimationFrame>, looping: Looping) -> Self {
+		Self { frames, looping }
+	}
+	fn cycle_length(&self) -> usize {
+		self.frames.iter().map(|frame| frame.duration.max(1)).sum()
+	}
+	pub fn frame_index(&self, time: usize) -> usize {
+		let cycle_length = self.cycle_length();
+		if cycle_length == 0 {
+			return 0;
+		}
+		let time = match self.looping {
+			Looping::Loop => time % cycle_length,
+			Looping::PingPong => {
+				let time = time % (cycle_length * 2);
+				if time < cycle_length {
+					time
+				} else {
+					(cycle_length * 2) - time - 1
+				}
+			}
+		};
+		let mut elapsed = 0;
+		for (index, frame) in self.frames.iter().enumerate() {
+			elapsed += frame.duration.max(1);
+			if time < elapsed {
+				return index;
+			}
+		}
+		self.frames.len() - 1
+	}
+	pub fn texture_at(&self, time: usize) -> Option<&Texture> {
+		self.frames
+			.get(self.frame_index(time))
+			.map(|frame| &frame.texture)
+	}
+}
+
+pub fn phase_offset(pos: &TilePos) -> usize {
+	// Stable per-tile so neighbouring animations don't move in lockstep
+	let hash = (pos.x as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+		^ (pos.y as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+	(hash >> 32) as usize
+}
@@ -4,6 +4,5 @@ pub mod tiles;
 
 pub use {
 	other::*,
-	resources::*,
 	tiles::*,
 };
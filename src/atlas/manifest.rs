@@ -0,0 +1,342 @@
+use {
+	super::TextureType,
+	crate::{
+		animation::Looping,
+		tile::GroundType,
+		world::autotile::Piece,
+	},
+	serde::Deserialize,
+	std::{
+		collections::{
+			HashMap,
+			HashSet,
+		},
+		fmt,
+		fs,
+		path::{
+			Path,
+			PathBuf,
+		},
+	},
+};
+
+#[derive(Debug)]
+pub enum AtlasError {
+	Read {
+		path: PathBuf,
+		error: std::io::Error,
+	},
+	Parse {
+		path: PathBuf,
+		error: toml::de::Error,
+	},
+	UnknownSheet {
+		key: TextureType,
+		sheet: String,
+	},
+	SheetLoad {
+		sheet: String,
+		path: PathBuf,
+		error: String,
+	},
+	OutOfBounds {
+		key: TextureType,
+		sheet: String,
+		cell: (u32, u32),
+		sheet_cells: (u32, u32),
+	},
+	ZeroCellSize(String),
+	TooLarge {
+		size: (u32, u32),
+		max: (u32, u32),
+	},
+	Upload(String),
+	Write {
+		path: PathBuf,
+		error: String,
+	},
+	DuplicateKey(TextureType),
+	NoFrames(TextureType),
+	CellAndFrames(TextureType),
+	DurationMismatch {
+		key: TextureType,
+		frames: usize,
+		durations: usize,
+	},
+}
+impl fmt::Display for AtlasError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use AtlasError::*;
+		match self {
+			Read { path, error } => {
+				write!(f, "could not read atlas manifest {:?}: {}", path, error)
+			}
+			Parse { path, error } => {
+				write!(f, "could not parse atlas manifest {:?}: {}", path, error)
+			}
+			UnknownSheet { key, sheet } => {
+				write!(f, "sprite {:?} refers to undeclared sheet {:?}", key, sheet)
+			}
+			SheetLoad { sheet, path, error } => {
+				write!(
+					f,
+					"could not load sheet {:?} from {:?}: {}",
+					sheet, path, error
+				)
+			}
+			OutOfBounds {
+				key,
+				sheet,
+				cell,
+				sheet_cells,
+			} => write!(
+				f,
+				"sprite {:?} uses cell {:?} but sheet {:?} is only {:?} cells",
+				key, cell, sheet, sheet_cells
+			),
+			TooLarge { size, max } => write!(
+				f,
+				"packed atlas is {:?} but the largest texture allowed is {:?}",
+				size, max
+			),
+			Upload(error) => write!(f, "could not upload packed atlas: {}", error),
+			Write { path, error } => write!(f, "could not write {:?}: {}", path, error),
+			ZeroCellSize(sheet) => write!(f, "sheet {:?} has a cell size of zero", sheet),
+			DuplicateKey(key) => write!(f, "sprite {:?} is declared more than once", key),
+			NoFrames(key) => write!(f, "sprite {:?} has neither a cell nor frames", key),
+			CellAndFrames(key) => write!(f, "sprite {:?} has both a cell and frames", key),
+			DurationMismatch {
+				key,
+				frames,
+				durations,
+			} => write!(
+				f,
+				"sprite {:?} has {} frames but {} durations",
+				key, frames, durations
+			),
+		}
+	}
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SheetEntry {
+	pub path: PathBuf,
+	pub cell_size: u32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SpriteEntry {
+	pub key: TextureType,
+	pub sheet: String,
+	pub cell: Option<(u32, u32)>,
+	pub frames: Option<Vec<(u32, u32)>>,
+	pub duration: Option<usize>,
+	pub durations: Option<Vec<usize>>,
+	pub looping: Option<Looping>,
+}
+impl SpriteEntry {
+	pub fn cells(&self) -> Vec<(u32, u32)> {
+		match (&self.cell, &self.frames) {
+			(Some(cell), _) => vec![*cell],
+			(None, Some(frames)) => frames.clone(),
+			(None, None) => vec![],
+		}
+	}
+	pub fn is_animated(&self) -> bool {
+		self.frames.is_some()
+	}
+	pub fn frame_durations(&self) -> Vec<usize> {
+		self.durations
+			.clone()
+			.unwrap_or_else(|| vec![self.duration.unwrap_or(1); self.cells().len()])
+	}
+}
+
+// A standard LPC terrain block, expanded into one sprite per transition piece
+#[derive(Debug, Deserialize)]
+pub struct TransitionEntry {
+	pub ground: GroundType,
+	pub sheet: String,
+	pub origin: (u32, u32),
+}
+impl TransitionEntry {
+	pub fn sprites(&self) -> impl Iterator<Item = SpriteEntry> + '_ {
+		Piece::ALL.into_iter().map(move |piece| {
+			let (x, y) = piece.block_cell();
+			SpriteEntry {
+				key: TextureType::Transition(self.ground.clone(), piece),
+				sheet: self.sheet.clone(),
+				cell: Some((self.origin.0 + x, self.origin.1 + y)),
+				frames: None,
+				duration: None,
+				durations: None,
+				looping: None,
+			}
+		})
+	}
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Manifest {
+	#[serde(skip)]
+	pub path: PathBuf,
+	#[serde(skip)]
+	pub root: PathBuf,
+	pub sheets: HashMap<String, SheetEntry>,
+	pub sprites: Vec<SpriteEntry>,
+	#[serde(default)]
+	pub transitions: Vec<TransitionEntry>,
+}
+impl Manifest {
+	pub fn load(path: impl AsRef<Path>) -> Result<Self, AtlasError> {
+		let path = path.as_ref();
+		let source = fs::read_to_string(path).map_err(|error| AtlasError::Read {
+			path: path.to_path_buf(),
+			error,
+		})?;
+		let mut manifest: Manifest =
+			toml::from_str(&source).map_err(|error| AtlasError::Parse {
+				path: path.to_path_buf(),
+				error,
+			})?;
+		manifest.path = path.to_path_buf();
+		manifest.root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+		let transitions = manifest
+			.transitions
+			.iter()
+			.flat_map(TransitionEntry::sprites)
+			.collect::<Vec<SpriteEntry>>();
+		manifest.sprites.extend(transitions);
+		manifest.validate()?;
+		Ok(manifest)
+	}
+	pub fn validate(&self) -> Result<(), AtlasError> {
+		if let Some((name, _)) = self.sheets.iter().find(|(_, sheet)| sheet.cell_size == 0) {
+			return Err(AtlasError::ZeroCellSize(name.clone()));
+		}
+		let mut seen = HashSet::new();
+		for sprite in &self.sprites {
+			if !seen.insert(&sprite.key) {
+				return Err(AtlasError::DuplicateKey(sprite.key.clone()));
+			}
+			if !self.sheets.contains_key(&sprite.sheet) {
+				return Err(AtlasError::UnknownSheet {
+					key: sprite.key.clone(),
+					sheet: sprite.sheet.clone(),
+				});
+			}
+			match (&sprite.cell, &sprite.frames) {
+				(Some(_), Some(_)) => return Err(AtlasError::CellAndFrames(sprite.key.clone())),
+				(None, None) => return Err(AtlasError::NoFrames(sprite.key.clone())),
+				(None, Some(frames)) if frames.is_empty() => {
+					return Err(AtlasError::NoFrames(sprite.key.clone()))
+				}
+				_ => (),
+			}
+			let frames = sprite.cells().len();
+			let durations = sprite.frame_durations().len();
+			if frames != durations {
+				return Err(AtlasError::DurationMismatch {
+					key: sprite.key.clone(),
+					frames,
+					durations,
+				});
+			}
+		}
+		Ok(())
+	}
+	pub fn sheet_path(&self, sheet: &SheetEntry) -> PathBuf {
+		self.root.join(&sheet.path)
+	}
+	pub fn check_bounds(
+		&self,
+		sprite: &SpriteEntry,
+		sheet_dimensions: (u32, u32),
+	) -> Result<(), AtlasError> {
+		let cell_size = self.sheets[&sprite.sheet].cell_size;
+		let sheet_cells = (
+			sheet_dimensions.0 / cell_size,
+			sheet_dimensions.1 / cell_size,
+		);
+		match sprite
+			.cells()
+			.into_iter()
+			.find(|(x, y)| *x >= sheet_cells.0 || *y >= sheet_cells.1)
+		{
+			Some(cell) => Err(AtlasError::OutOfBounds {
+				key: sprite.key.clone(),
+				sheet: sprite.sheet.clone(),
+				cell,
+				sheet_cells,
+			}),
+			None => Ok(()),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		crate::tile::GroundType,
+	};
+
+	fn sprite(ground: &str, sheet: &str, cells: &str) -> String {
+		format!(
+			"[[sprites]]\nkey = {{ Ground = \"{}\" }}\nsheet = \"{}\"\n{}\n",
+			ground, sheet, cells
+		)
+	}
+
+	fn manifest(sprites: &[String]) -> Manifest {
+		let sheets = "[sheets.terrain]\npath = \"terrain.png\"\ncell_size = 32\n";
+		toml::from_str(&(sheets.to_string() + &sprites.concat())).unwrap()
+	}
+
+	#[test]
+	fn valid() {
+		let manifest = manifest(&[sprite("Dirt", "terrain", "cell = [1, 2]")]);
+		assert!(manifest.validate().is_ok());
+		assert!(manifest
+			.check_bounds(&manifest.sprites[0], (64, 96))
+			.is_ok());
+	}
+
+	#[test]
+	fn duplicate_key() {
+		let manifest = manifest(&[
+			sprite("Dirt", "terrain", "cell = [0, 0]"),
+			sprite("Water", "terrain", "cell = [1, 0]"),
+			sprite("Dirt", "terrain", "cell = [2, 0]"),
+		]);
+		assert!(matches!(
+			manifest.validate(),
+			Err(AtlasError::DuplicateKey(TextureType::Ground(
+				GroundType::Dirt
+			)))
+		));
+	}
+
+	#[test]
+	fn unknown_sheet() {
+		let manifest = manifest(&[sprite("Dirt", "rocks", "cell = [0, 0]")]);
+		assert!(matches!(
+			manifest.validate(),
+			Err(AtlasError::UnknownSheet { sheet, .. }) if sheet == "rocks"
+		));
+	}
+
+	#[test]
+	fn cell_out_of_bounds() {
+		let manifest = manifest(&[sprite("Water", "terrain", "frames = [[0, 0], [2, 0]]")]);
+		assert!(manifest.validate().is_ok()); // Only known once the sheet is loaded
+		assert!(matches!(
+			manifest.check_bounds(&manifest.sprites[0], (64, 32)),
+			Err(AtlasError::OutOfBounds {
+				cell: (2, 0),
+				sheet_cells: (2, 1),
+				..
+			})
+		));
+	}
+}
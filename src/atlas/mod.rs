@@ -0,0 +1,173 @@
+use {
+	crate::{
+		animation::{
+			Animation,
+			AnimationFrame,
+			Looping,
+		},
+		frame_manager::draw::Draw,
+		tile::*,
+		world::{
+			autotile::Piece,
+			season::Season,
+		},
+	},
+	crow::{
+		Context,
+		Texture,
+	},
+	serde::Deserialize,
+	std::{
+		collections::HashMap,
+		env,
+		path::{
+			Path,
+			PathBuf,
+		},
+	},
+};
+
+pub mod manifest;
+pub mod packer;
+pub mod watcher;
+pub use {
+	manifest::{
+		AtlasError,
+		Manifest,
+	},
+	packer::PackedAtlas,
+	watcher::{
+		AssetChange,
+		AssetWatcher,
+	},
+};
+
+const MANIFEST_FILE: &str = "atlas.toml";
+pub const MANIFEST_VAR: &str = "KY_SPACE_ATLAS"; // Overrides where the manifest is looked for
+
+// $KY_SPACE_ATLAS if set, otherwise next to the executable if it's there, otherwise src/ of the
+// working directory as when run from the repo
+pub fn manifest_path() -> PathBuf {
+	if let Some(path) = env::var_os(MANIFEST_VAR) {
+		return path.into();
+	}
+	env::current_exe()
+		.ok()
+		.and_then(|exe| Some(exe.parent()?.join(MANIFEST_FILE)))
+		.filter(|path| path.is_file())
+		.unwrap_or_else(|| Path::new("src").join(MANIFEST_FILE))
+}
+
+pub enum SpriteTexture {
+	Still(Texture),
+	Animated(Animation),
+}
+impl SpriteTexture {
+	pub fn texture_at(&self, time: usize) -> Option<&Texture> {
+		match self {
+			SpriteTexture::Still(texture) => Some(texture),
+			SpriteTexture::Animated(animation) => animation.texture_at(time),
+		}
+	}
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Deserialize)]
+pub enum TextureType {
+	Ground(GroundType),
+	Resource(ResourceType),
+	Other(OtherTexture),
+	AnimatedOther(OtherTexture),
+	AnimatedGround(GroundType),
+	Transition(GroundType, Piece),
+	SnowyGround(GroundType),
+	SnowyResource(ResourceType),
+	SeasonalGround {
+		ground: GroundType,
+		season: Season,
+	},
+	SeasonalResource {
+		resource: ResourceType,
+		season: Season,
+	},
+}
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Deserialize)]
+pub enum OtherTexture {
+	PlayerUp,
+	PlayerDown,
+	PlayerLeft,
+	PlayerRight,
+}
+pub struct Atlas {
+	pub atlas: HashMap<TextureType, SpriteTexture>,
+	pub manifest: Manifest,
+}
+impl Atlas {
+	pub fn contains(&self, drawable: &impl Draw) -> bool {
+		drawable
+			.texture_type()
+			.is_some_and(|texture_type| self.atlas.contains_key(&texture_type))
+	}
+	pub fn new(context: &mut Context) -> Result<Self, AtlasError> {
+		Self::load(context, manifest_path())
+	}
+	pub fn load(context: &mut Context, path: impl AsRef<Path>) -> Result<Self, AtlasError> {
+		let manifest = Manifest::load(path)?;
+		let atlas = Self::build(context, &manifest)?;
+		Ok(Self { atlas, manifest })
+	}
+	// Repacks every sprite, as they all share one texture, keeping the current ones if anything
+	// fails to load
+	pub fn reload(&mut self, context: &mut Context) -> Result<(), AtlasError> {
+		let manifest = Manifest::load(&self.manifest.path)?;
+		self.atlas = Self::build(context, &manifest)?;
+		self.manifest = manifest;
+		Ok(())
+	}
+	fn build(
+		context: &mut Context,
+		manifest: &Manifest,
+	) -> Result<HashMap<TextureType, SpriteTexture>, AtlasError> {
+		let PackedAtlas { image, rects } = PackedAtlas::pack(manifest)?;
+		let (width, height) = image.dimensions();
+		let (max_width, max_height) = context.maximum_texture_size();
+		if width > max_width || height > max_height {
+			return Err(AtlasError::TooLarge {
+				size: (width, height),
+				max: (max_width, max_height),
+			});
+		}
+		let texture = Texture::from_image(context, image)
+			.map_err(|error| AtlasError::Upload(error.to_string()))?;
+		let atlas = manifest
+			.sprites
+			.iter()
+			.map(|sprite| {
+				// Packed rects count from the top, textures from the bottom
+				let mut textures = rects[&sprite.key].iter().map(|rect| {
+					texture.get_section(
+						(rect.x, height - rect.y - rect.height),
+						(rect.width, rect.height),
+					)
+				});
+				let texture = if sprite.is_animated() {
+					let frames = textures
+						.zip(sprite.frame_durations())
+						.map(|(texture, duration)| AnimationFrame { texture, duration })
+						.collect();
+					SpriteTexture::Animated(Animation::new(
+						frames,
+						sprite.looping.unwrap_or(Looping::Loop),
+					))
+				} else {
+					SpriteTexture::Still(
+						textures
+							.next()
+							.ok_or_else(|| AtlasError::NoFrames(sprite.key.clone()))?,
+					)
+				};
+				Ok((sprite.key.clone(), texture))
+			})
+			.collect::<Result<HashMap<TextureType, SpriteTexture>, AtlasError>>()?;
+		Ok(atlas)
+	}
+}
@@ -0,0 +1,177 @@
+use {
+	super::{
+		manifest::{
+			AtlasError,
+			Manifest,
+		},
+		TextureType,
+	},
+	crow::image::{
+		self,
+		GenericImage,
+		GenericImageView,
+		RgbaImage,
+	},
+	serde::Serialize,
+	std::{
+		collections::HashMap,
+		fs,
+		path::Path,
+	},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Rect {
+	pub x: u32,
+	pub y: u32,
+	pub width: u32,
+	pub height: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndexEntry {
+	pub key: String,
+	pub frames: Vec<Rect>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AtlasIndex {
+	pub width: u32,
+	pub height: u32,
+	pub sprites: Vec<IndexEntry>,
+}
+
+pub struct PackedAtlas {
+	pub image: RgbaImage,
+	pub rects: HashMap<TextureType, Vec<Rect>>,
+}
+impl PackedAtlas {
+	// Copies only the cells the manifest uses into one image, packed in shelves
+	pub fn pack(manifest: &Manifest) -> Result<Self, AtlasError> {
+		let sheets = manifest
+			.sheets
+			.iter()
+			.map(|(name, sheet)| {
+				let path = manifest.sheet_path(sheet);
+				image::open(&path)
+					.map(|image| (name, image.to_rgba8()))
+					.map_err(|error| AtlasError::SheetLoad {
+						sheet: name.clone(),
+						path,
+						error: error.to_string(),
+					})
+			})
+			.collect::<Result<HashMap<&String, RgbaImage>, AtlasError>>()?;
+
+		// Each distinct cell is copied once, even if several sprites share it
+		let mut cells: Vec<(&String, (u32, u32))> = vec![];
+		for sprite in &manifest.sprites {
+			manifest.check_bounds(sprite, sheets[&sprite.sheet].dimensions())?;
+			for cell in sprite.cells() {
+				if !cells.contains(&(&sprite.sheet, cell)) {
+					cells.push((&sprite.sheet, cell));
+				}
+			}
+		}
+		let cell_size = |sheet: &String| manifest.sheets[sheet].cell_size;
+		cells.sort_by_key(|(sheet, cell)| (std::cmp::Reverse(cell_size(sheet)), *sheet, *cell));
+
+		let area: u32 = cells.iter().map(|(sheet, _)| cell_size(sheet).pow(2)).sum();
+		let widest = cells
+			.iter()
+			.map(|(sheet, _)| cell_size(sheet))
+			.max()
+			.unwrap_or(1);
+		let width = ((area as f64).sqrt() as u32)
+			.max(widest)
+			.next_power_of_two();
+
+		let mut placed: HashMap<(&String, (u32, u32)), Rect> = HashMap::new();
+		let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+		for (sheet, cell) in &cells {
+			let size = cell_size(sheet);
+			if x + size > width {
+				x = 0;
+				y += shelf_height;
+				shelf_height = 0;
+			}
+			placed.insert(
+				(*sheet, *cell),
+				Rect {
+					x,
+					y,
+					width: size,
+					height: size,
+				},
+			);
+			x += size;
+			shelf_height = shelf_height.max(size);
+		}
+		let height = (y + shelf_height).max(1).next_power_of_two();
+
+		let mut image = RgbaImage::new(width, height);
+		for ((sheet, (cell_x, cell_y)), rect) in &placed {
+			let section = sheets[sheet].view(
+				cell_x * rect.width,
+				cell_y * rect.height,
+				rect.width,
+				rect.height,
+			);
+			image
+				.copy_from(&section, rect.x, rect.y)
+				.expect("Packed cell fits inside packed atlas");
+		}
+
+		let rects = manifest
+			.sprites
+			.iter()
+			.map(|sprite| {
+				let frames = sprite
+					.cells()
+					.into_iter()
+					.map(|cell| placed[&(&sprite.sheet, cell)])
+					.collect();
+				(sprite.key.clone(), frames)
+			})
+			.collect();
+		Ok(Self { image, rects })
+	}
+	pub fn index(&self) -> AtlasIndex {
+		let mut sprites: Vec<IndexEntry> = self
+			.rects
+			.iter()
+			.map(|(key, frames)| IndexEntry {
+				key: format!("{:?}", key),
+				frames: frames.clone(),
+			})
+			.collect();
+		sprites.sort_by(|a, b| a.key.cmp(&b.key));
+		AtlasIndex {
+			width: self.image.width(),
+			height: self.image.height(),
+			sprites,
+		}
+	}
+	pub fn write(
+		&self,
+		image_path: impl AsRef<Path>,
+		index_path: impl AsRef<Path>,
+	) -> Result<(), AtlasError> {
+		let image_path = image_path.as_ref();
+		self.image
+			.save(image_path)
+			.map_err(|error| AtlasError::Write {
+				path: image_path.to_path_buf(),
+				error: error.to_string(),
+			})?;
+		let index_path = index_path.as_ref();
+		let index = toml::to_string(&self.index()).map_err(|error| AtlasError::Write {
+			path: index_path.to_path_buf(),
+			error: error.to_string(),
+		})?;
+		fs::write(index_path, index).map_err(|error| AtlasError::Write {
+			path: index_path.to_path_buf(),
+			error: error.to_string(),
+		})
+	}
+}
@@ -0,0 +1,120 @@
+use {
+	super::Manifest,
+	std::{
+		collections::HashMap,
+		fs,
+		path::PathBuf,
+		time::{
+			Duration,
+			Instant,
+			SystemTime,
+		},
+	},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AssetChange {
+	Manifest,
+	Sheets(Vec<String>),
+}
+
+// Polls modification times of the manifest and its sheets, for development
+pub struct AssetWatcher {
+	modified: HashMap<PathBuf, Option<SystemTime>>,
+	last_poll: Instant,
+}
+impl AssetWatcher {
+	pub fn new(manifest: &Manifest) -> Self {
+		let mut watcher = Self {
+			modified: HashMap::new(),
+			last_poll: Instant::now(),
+		};
+		watcher.watch(manifest);
+		watcher
+	}
+	// Forget old timestamps and watch whatever `manifest` now refers to
+	pub fn watch(&mut self, manifest: &Manifest) {
+		self.modified = Self::watched_paths(manifest)
+			.into_iter()
+			.map(|(path, _)| {
+				let modified = Self::modified(&path);
+				(path, modified)
+			})
+			.collect();
+	}
+	// What's changed since `watch` was last called, so a failed reload is tried again
+	pub fn poll(&mut self, manifest: &Manifest) -> Option<AssetChange> {
+		if self.last_poll.elapsed() < POLL_INTERVAL {
+			return None;
+		}
+		self.last_poll = Instant::now();
+
+		let mut manifest_changed = false;
+		let mut sheets = vec![];
+		for (path, sheet) in Self::watched_paths(manifest) {
+			let modified = Self::modified(&path);
+			if self.modified.get(&path) != Some(&modified) {
+				match sheet {
+					Some(sheet) => sheets.push(sheet),
+					None => manifest_changed = true,
+				}
+			}
+		}
+		if manifest_changed {
+			Some(AssetChange::Manifest)
+		} else if !sheets.is_empty() {
+			Some(AssetChange::Sheets(sheets))
+		} else {
+			None
+		}
+	}
+	fn watched_paths(manifest: &Manifest) -> Vec<(PathBuf, Option<String>)> {
+		std::iter::once((manifest.path.clone(), None))
+			.chain(
+				manifest
+					.sheets
+					.iter()
+					.map(|(name, sheet)| (manifest.sheet_path(sheet), Some(name.clone()))),
+			)
+			.collect()
+	}
+	fn modified(path: &PathBuf) -> Option<SystemTime> {
+		fs::metadata(path)
+			.and_then(|metadata| metadata.modified())
+			.ok()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		std::fs::File,
+	};
+
+	#[test]
+	fn kept_until_watched() {
+		let path = std::env::temp_dir().join(format!("ky_space_{}_atlas.toml", std::process::id()));
+		fs::write(&path, "sprites = []\n[sheets]\n").unwrap();
+		let manifest = Manifest::load(&path).unwrap();
+		let mut watcher = AssetWatcher::new(&manifest);
+		let poll = |watcher: &mut AssetWatcher| {
+			watcher.last_poll -= POLL_INTERVAL;
+			watcher.poll(&manifest)
+		};
+		assert_eq!(poll(&mut watcher), None);
+		File::options()
+			.write(true)
+			.open(&path)
+			.and_then(|file| file.set_modified(SystemTime::now() + Duration::from_secs(60)))
+			.unwrap();
+		assert_eq!(poll(&mut watcher), Some(AssetChange::Manifest));
+		// As if the reload failed
+		assert_eq!(poll(&mut watcher), Some(AssetChange::Manifest));
+		watcher.watch(&manifest);
+		assert_eq!(poll(&mut watcher), None);
+		fs::remove_file(&path).unwrap();
+	}
+}
@@ -0,0 +1,225 @@
+use {
+	crate::{
+		atlas::{
+			manifest_path,
+			Manifest,
+			PackedAtlas,
+		},
+		replay::{
+			Replay,
+			ReplayError,
+		},
+		scheduler::SCALES,
+		world::{
+			clock::Clock,
+			layer::{
+				Depth,
+				SURFACE,
+			},
+			tile::TilePos,
+			World,
+		},
+		Error,
+		GROUND_SEED,
+		RESOURCE_SEED,
+	},
+	std::path::PathBuf,
+};
+
+pub const USAGE: &str = "Usage:
+	ky_space [--dev] [--speed <scale>] [--day-length <minutes>] [--season <season>] [--record <replay> | --save <dir>]
+	                                          Play, --dev reloads sprites when they change and logs events,
+	                                          --speed runs game time faster or slower, 0.05 to 16,
+	                                          --day-length sets how long a full day and night lasts,
+	                                          --season stays in spring, summer, autumn or winter,
+	                                          --record saves the session for replaying,
+	                                          --save continues the world saved in dir, or starts one there,
+	                                          saving as you play. The saved day length and season are kept.
+	ky_space replay <replay>                  Rerun a recorded session and check it ends the same
+	ky_space pack-atlas [image] [index]       Write the packed sprite atlas and its index
+	ky_space export-map <x> <y> <width> <height> [image] [--seed <ground> <resource>] [--depth <depth>]
+	                                          Write a region of the world as a PNG, a pixel per tile,
+	                                          --depth 1 shows the caves";
+
+pub enum Command {
+	Play {
+		dev: bool,
+		speed: f64,
+		clock: Clock,
+		record: Option<PathBuf>,
+		save: Option<PathBuf>,
+	},
+	Replay {
+		path: PathBuf,
+	},
+	PackAtlas {
+		image: PathBuf,
+		index: PathBuf,
+	},
+	ExportMap {
+		ground_seed: String,
+		resource_seed: String,
+		depth: Depth,
+		pos: TilePos, // South west corner
+		size: TilePos,
+		image: PathBuf,
+	},
+}
+impl Command {
+	pub fn from_args(args: impl Iterator<Item = String>) -> Result<Self, &'static str> {
+		let mut args = args.peekable();
+		if args.peek().map(String::as_str) == Some("replay") {
+			args.next();
+			return match (args.next(), args.next()) {
+				(Some(path), None) => Ok(Command::Replay { path: path.into() }),
+				_ => Err(USAGE),
+			};
+		}
+		if args.peek().map(String::as_str) == Some("pack-atlas") {
+			args.next();
+			return Ok(Command::PackAtlas {
+				image: args.next().unwrap_or_else(|| "atlas.png".into()).into(),
+				index: args
+					.next()
+					.unwrap_or_else(|| "atlas-index.toml".into())
+					.into(),
+			});
+		}
+		if args.peek().map(String::as_str) == Some("export-map") {
+			args.next();
+			let mut region = [0; 4];
+			for number in region.iter_mut() {
+				*number = args
+					.next()
+					.and_then(|number| number.parse().ok())
+					.ok_or(USAGE)?;
+			}
+			let [x, y, width, height] = region;
+			if width <= 0 || height <= 0 {
+				return Err(USAGE);
+			}
+			let (mut ground_seed, mut resource_seed) = (GROUND_SEED.into(), RESOURCE_SEED.into());
+			let (mut depth, mut image) = (SURFACE, PathBuf::from("map.png"));
+			while let Some(arg) = args.next() {
+				match arg.as_str() {
+					"--seed" => {
+						ground_seed = args.next().ok_or(USAGE)?;
+						resource_seed = args.next().ok_or(USAGE)?;
+					}
+					"--depth" => {
+						depth = args
+							.next()
+							.and_then(|depth| depth.parse().ok())
+							.ok_or(USAGE)?;
+					}
+					_ if !arg.starts_with("--") => image = arg.into(),
+					_ => return Err(USAGE),
+				}
+			}
+			return Ok(Command::ExportMap {
+				ground_seed,
+				resource_seed,
+				depth,
+				pos: (x, y).into(),
+				size: (width, height).into(),
+				image,
+			});
+		}
+		let (mut dev, mut speed, mut clock) = (false, 1.0, Clock::default());
+		let (mut record, mut save) = (None, None);
+		while let Some(arg) = args.next() {
+			match arg.as_str() {
+				"--dev" => dev = true,
+				"--speed" => {
+					speed = args
+						.next()
+						.and_then(|scale| scale.parse().ok())
+						.filter(|scale| SCALES.contains(scale))
+						.ok_or(USAGE)?;
+				}
+				"--day-length" => {
+					let minutes = args
+						.next()
+						.and_then(|minutes| minutes.parse().ok())
+						.filter(|minutes| *minutes > 0)
+						.ok_or(USAGE)?;
+					clock = Clock {
+						forced_season: clock.forced_season,
+						..Clock::from_minutes(minutes)
+					};
+				}
+				"--season" => {
+					clock.forced_season = Some(
+						args.next()
+							.and_then(|season| season.parse().ok())
+							.ok_or(USAGE)?,
+					);
+				}
+				"--record" => record = Some(args.next().ok_or(USAGE)?.into()),
+				"--save" => save = Some(args.next().ok_or(USAGE)?.into()),
+				_ => return Err(USAGE),
+			}
+		}
+		if record.is_some() && save.is_some() {
+			return Err(USAGE); // Replays start from a new world
+		}
+		Ok(Command::Play {
+			dev,
+			speed,
+			clock,
+			record,
+			save,
+		})
+	}
+}
+
+pub fn replay(path: PathBuf) -> Result<(), ReplayError> {
+	let replay = Replay::load(&path)?;
+	replay.verify()?;
+	println!(
+		"Replay {:?} matches: {} inputs over {} ticks, world hash {}",
+		path,
+		replay.inputs.len(),
+		replay.final_tick,
+		replay.world_hash
+	);
+	Ok(())
+}
+
+pub fn pack_atlas(image: PathBuf, index: PathBuf) -> Result<(), Error> {
+	let manifest = Manifest::load(manifest_path())?;
+	let packed = PackedAtlas::pack(&manifest)?;
+	packed.write(&image, &index)?;
+	println!(
+		"Packed {} sprites into {:?} ({}x{}), index at {:?}",
+		manifest.sprites.len(),
+		image,
+		packed.image.width(),
+		packed.image.height(),
+		index
+	);
+	Ok(())
+}
+
+// Chunks that fail to generate are reported and left fogged, the rest is still written
+pub fn export_map(
+	ground_seed: String,
+	resource_seed: String,
+	depth: Depth,
+	pos: TilePos,
+	size: TilePos,
+	image: PathBuf,
+) -> Result<(), Error> {
+	let mut world = World::new(&ground_seed, &resource_seed);
+	let (map, errors) = world.export_map(depth, pos, size);
+	errors.iter().for_each(|error| eprintln!("{}", error));
+	map.image.save(&image).map_err(|error| Error::ExportMap {
+		path: image.clone(),
+		error: error.to_string(),
+	})?;
+	println!(
+		"Exported {}x{} tiles from {:?} at depth {} of seed {:?} {:?} to {:?}",
+		size.x, size.y, pos, depth, ground_seed, resource_seed, image
+	);
+	Ok(())
+}
@@ -1,76 +1,398 @@
-use crow::glutin::event::{
-	MouseButton,
-	VirtualKeyCode,
+use {
+	crow::glutin::event::{
+		MouseButton,
+		VirtualKeyCode,
+	},
+	serde::{
+		Deserialize,
+		Serialize,
+	},
+	std::{
+		collections::{
+			HashSet,
+			VecDeque,
+		},
+		fmt,
+	},
 };
 
 use crate::{
+	input_map::{
+		self,
+		Binding,
+		Bound,
+		Conflict,
+		GameCommand,
+		Input,
+		InputMap,
+		Modifiers,
+	},
 	world::tile::TilePos,
 	MOVE_TIME,
 };
 
+// Input as read by the render thread, applied to the controls on the tick thread
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum ControlInput {
+	Click {
+		button: MouseButton,
+		modifiers: Modifiers,
+		cursor: TilePos,
+	},
+	Press {
+		keycode: VirtualKeyCode,
+		modifiers: Modifiers,
+		cursor: TilePos,
+	},
+	Release {
+		keycode: VirtualKeyCode,
+	},
+	ReleaseAll,
+	Command {
+		command: GameCommand,
+		cursor: TilePos,
+	},
+	GamepadDirection {
+		direction: TilePos,
+	},
+}
+
+impl ControlInput {
+	// Where the input was aimed, for those that have a cursor
+	pub fn cursor(&self) -> Option<TilePos> {
+		use ControlInput::*;
+		match self {
+			Click { cursor, .. } | Press { cursor, .. } | Command { cursor, .. } => Some(*cursor),
+			Release { .. } | ReleaseAll | GamepadDirection { .. } => None,
+		}
+	}
+}
+
+// Set while the next inputs pick a binding and then its replacement
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Rebinding {
+	Choosing,
+	Replacing(Bound),
+}
+
 #[derive(Debug)]
 pub struct ControlManager {
-	pub pending_action: Option<PlayerAction>,
+	pub actions: VecDeque<PlayerAction>,
+	pub input_map: InputMap,
+	held_keys: HashSet<VirtualKeyCode>,
+	gamepad_direction: TilePos,
+	rebinding: Option<Rebinding>,
+	rebind_events: Vec<RebindEvent>,
+	events: Vec<ActionEvent>,
+}
+impl Default for ControlManager {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 impl ControlManager {
 	pub fn new() -> Self {
+		Self::with_input_map(InputMap::load_or_default())
+	}
+	pub fn with_input_map(input_map: InputMap) -> Self {
 		Self {
-			pending_action: None,
+			actions: VecDeque::new(),
+			input_map,
+			held_keys: HashSet::new(),
+			gamepad_direction: (0, 0).into(),
+			rebinding: None,
+			rebind_events: vec![],
+			events: vec![],
 		}
 	}
-	pub fn get_pending(&mut self) -> &Option<PlayerAction> {
-		&self.pending_action
+	pub fn current(&self) -> Option<&PlayerAction> {
+		self.actions.front()
+	}
+	pub fn current_mut(&mut self) -> Option<&mut PlayerAction> {
+		self.actions.front_mut()
+	}
+	pub fn enqueue(&mut self, action: Action) {
+		self.actions.push_back(PlayerAction::new(action));
 	}
-	pub fn complete_pending(&mut self) -> Option<PlayerAction> {
-		self.pending_action.take()
+	// Drops everything queued in favour of `action`
+	pub fn replace(&mut self, action: Action) {
+		self.clear();
+		self.enqueue(action);
 	}
-	pub fn click(&mut self, button: MouseButton, pos: TilePos) {
-		if button == MouseButton::Left {
-			self.pending_action = Some(PlayerAction::new(Action::MoveTo(pos)));
+	pub fn start_current(&mut self) {
+		if let Some(player_action) = self.actions.front_mut() {
+			if !player_action.started {
+				player_action.started = true;
+				self.events
+					.push(ActionEvent::Started(player_action.action.clone()));
+			}
 		}
 	}
-	pub fn press(&mut self, keycode: VirtualKeyCode, pos: TilePos) {
-		self.pending_action = match keycode {
-			VirtualKeyCode::Right | VirtualKeyCode::D => {
-				Some(PlayerAction::new(Action::MoveTo(pos + &(1, 0).into())))
+	pub fn complete_current(&mut self) -> Option<PlayerAction> {
+		let player_action = self.actions.pop_front()?;
+		self.events
+			.push(ActionEvent::Completed(player_action.action.clone()));
+		Some(player_action)
+	}
+	pub fn fail_current(&mut self, reason: ActionFailure) -> Option<PlayerAction> {
+		let player_action = self.actions.pop_front()?;
+		self.events
+			.push(ActionEvent::Failed(player_action.action.clone(), reason));
+		Some(player_action)
+	}
+	pub fn cancel_current(&mut self) -> Option<PlayerAction> {
+		self.fail_current(ActionFailure::Cancelled)
+	}
+	pub fn clear(&mut self) {
+		while self.cancel_current().is_some() {}
+	}
+	pub fn drain_events(&mut self) -> Vec<ActionEvent> {
+		std::mem::take(&mut self.events)
+	}
+	pub fn drain_rebind_events(&mut self) -> Vec<RebindEvent> {
+		std::mem::take(&mut self.rebind_events)
+	}
+	// Returns the command acted on, if any
+	pub fn apply(&mut self, input: ControlInput) -> Option<GameCommand> {
+		use ControlInput::*;
+		match input {
+			Click {
+				button,
+				modifiers,
+				cursor,
+			} => self.click(button, modifiers, cursor),
+			Press {
+				keycode,
+				modifiers,
+				cursor,
+			} => self.press(keycode, modifiers, cursor),
+			Release { keycode } => {
+				self.release(keycode);
+				None
 			}
-			VirtualKeyCode::Left | VirtualKeyCode::A => {
-				Some(PlayerAction::new(Action::MoveTo(pos + &(-1, 0).into())))
+			ReleaseAll => {
+				self.release_all();
+				None
 			}
-			VirtualKeyCode::Up | VirtualKeyCode::W => {
-				Some(PlayerAction::new(Action::MoveTo(pos + &(0, 1).into())))
+			Command { command, cursor } => {
+				self.command(command, cursor);
+				Some(command)
 			}
-			VirtualKeyCode::Down | VirtualKeyCode::S => {
-				Some(PlayerAction::new(Action::MoveTo(pos + &(0, -1).into())))
+			GamepadDirection { direction } => {
+				self.set_gamepad_direction(direction);
+				None
 			}
-			_ => None,
+		}
+	}
+	pub fn click(
+		&mut self,
+		button: MouseButton,
+		modifiers: Modifiers,
+		cursor: TilePos,
+	) -> Option<GameCommand> {
+		if self.rebinding.is_some() {
+			self.rebind_with(Binding {
+				input: Input::Mouse(button),
+				modifiers,
+			});
+			return None;
+		}
+		let command = self.input_map.command(Input::Mouse(button), modifiers)?;
+		self.command(command, cursor);
+		Some(command)
+	}
+	pub fn press(
+		&mut self,
+		keycode: VirtualKeyCode,
+		modifiers: Modifiers,
+		cursor: TilePos,
+	) -> Option<GameCommand> {
+		// The OS repeats held keys, only the first press counts
+		if !self.held_keys.insert(keycode) {
+			return None;
+		}
+		if self.rebinding.is_some() {
+			if !input_map::is_modifier(keycode) {
+				self.rebind_with(Binding {
+					input: Input::Key(keycode),
+					modifiers,
+				});
+			}
+			return None;
+		}
+		let command = self.input_map.command(Input::Key(keycode), modifiers)?;
+		self.command(command, cursor);
+		Some(command)
+	}
+	pub fn release(&mut self, keycode: VirtualKeyCode) {
+		self.held_keys.remove(&keycode);
+	}
+	// Releases aren't seen while the window is unfocused
+	pub fn release_all(&mut self) {
+		self.held_keys.clear();
+	}
+	// Combined direction of held movement keys and the gamepad, opposite keys cancel out
+	pub fn held_direction(&self) -> TilePos {
+		let (x, y) = self
+			.held_keys
+			.iter()
+			.filter_map(|keycode| {
+				self.input_map
+					.command(Input::Key(*keycode), Modifiers::default())
+			})
+			.filter_map(|command| command.direction())
+			.fold(
+				(self.gamepad_direction.x, self.gamepad_direction.y),
+				|(x, y), direction| (x + direction.x, y + direction.y),
+			);
+		(x.signum(), y.signum()).into()
+	}
+	pub fn command(&mut self, command: GameCommand, cursor: TilePos) {
+		use GameCommand::*;
+		match command {
+			MoveRight | MoveLeft | MoveUp | MoveDown => {
+				if let Some(direction) = command.direction() {
+					self.walk(direction);
+				}
+			}
+			MoveToCursor => self.replace(Action::MoveTo(cursor)),
+			QueueMoveToCursor => self.enqueue(Action::MoveTo(cursor)),
+			CancelAction => {
+				self.cancel_current();
+			}
+			ClearActions => self.clear(),
+			Interact => self.replace(Action::Harvest(cursor)),
+			Attack | Inventory => (), // Nothing to act on yet
+			TogglePause => (),        // Game time belongs to the scheduler
+			Rebind => {
+				self.rebinding = Some(Rebinding::Choosing);
+				self.rebind_events.push(RebindEvent::Started);
+			}
+			ToggleMap => (),      // Only changes what's drawn
+			ToggleWaypoint => (), // Marks the world, the player doesn't act
+		}
+	}
+	// Picks the binding to change, then replaces it. Nothing bound to the first input cancels.
+	fn rebind_with(&mut self, binding: Binding) {
+		let event = match self.rebinding.take() {
+			Some(Rebinding::Choosing) => {
+				match self.input_map.bound(binding.input, binding.modifiers) {
+					Some(bound) => {
+						self.rebinding = Some(Rebinding::Replacing(bound));
+						RebindEvent::Chosen(bound)
+					}
+					None => RebindEvent::Cancelled,
+				}
+			}
+			Some(Rebinding::Replacing(bound)) => match self.input_map.replace(bound, binding) {
+				Ok(()) => RebindEvent::Rebound {
+					command: bound.command,
+					from: bound.binding,
+					to: binding,
+				},
+				Err(conflict) => RebindEvent::Failed(conflict),
+			},
+			None => return,
 		};
+		self.rebind_events.push(event);
+	}
+	// Already walking picks up the new direction from what's held
+	pub fn walk(&mut self, first_step: TilePos) {
+		if !matches!(
+			self.current(),
+			Some(PlayerAction {
+				action: Action::Walk { .. },
+				..
+			})
+		) {
+			self.replace(Action::Walk {
+				first_step: Some(first_step),
+			});
+		}
+	}
+	pub fn set_gamepad_direction(&mut self, direction: TilePos) {
+		self.gamepad_direction = direction;
+		if direction != (0, 0).into() {
+			self.walk(direction);
+		}
 	}
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RebindEvent {
+	Started,
+	Chosen(Bound),
+	Rebound {
+		command: GameCommand,
+		from: Binding,
+		to: Binding,
+	},
+	Failed(Conflict), // Nothing changed
+	Cancelled,
+}
+impl fmt::Display for RebindEvent {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use RebindEvent::*;
+		match self {
+			Started => write!(f, "Press the key or button to rebind"),
+			Chosen(bound) => write!(
+				f,
+				"Press the new key or button for {:?}, replacing {}",
+				bound.command, bound.binding
+			),
+			Rebound { command, from, to } => {
+				write!(f, "{:?} moved from {} to {}", command, from, to)
+			}
+			Failed(conflict) => write!(f, "Could not rebind, {}", conflict),
+			Cancelled => write!(f, "Nothing is bound to that, rebinding cancelled"),
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum Action {
 	MoveTo(TilePos),
+	Walk { first_step: Option<TilePos> }, // Follows held keys, `first_step` is taken even if released early
+	Harvest(TilePos),                     // Walks next to it first
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActionFailure {
+	Cancelled,
+	NothingToHarvest,
+	Blocked, // Something unwalkable is in the way
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ActionEvent {
+	Started(Action),
+	Completed(Action),
+	Failed(Action, ActionFailure),
 }
 
 #[derive(Debug)]
 pub struct PlayerAction {
 	pub action: Action,
 	pub countdown: u64,
+	pub started: bool,
 }
 impl PlayerAction {
 	pub fn new(action: Action) -> Self {
 		let mut new = Self {
 			action,
 			countdown: 0,
+			started: false,
 		};
-		new.reset_countdown();
+		if !matches!(new.action, Action::Walk { .. }) {
+			new.reset_countdown(); // Walking steps straight away
+		}
 		new
 	}
 	fn reset_countdown(&mut self) {
 		use Action::*;
 		self.countdown = match &self.action {
-			MoveTo(..) => MOVE_TIME,
+			MoveTo(..) | Walk { .. } | Harvest(..) => MOVE_TIME,
 		};
 	}
 	pub fn tick(&mut self) -> bool {
@@ -83,3 +405,195 @@ impl PlayerAction {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		VirtualKeyCode::*,
+	};
+
+	fn press(control_manager: &mut ControlManager, keycode: VirtualKeyCode) {
+		control_manager.press(keycode, Modifiers::default(), (0, 0).into());
+		control_manager.release(keycode);
+	}
+
+	#[test]
+	fn rebinding() {
+		let mut control_manager = ControlManager::with_input_map(InputMap::default());
+		press(&mut control_manager, F1);
+		press(&mut control_manager, LShift); // Waits for what it's held with
+		press(&mut control_manager, W);
+		press(&mut control_manager, S); // Taken by MoveDown
+		press(&mut control_manager, F1);
+		press(&mut control_manager, W);
+		press(&mut control_manager, K);
+		assert!(control_manager.actions.is_empty());
+		press(&mut control_manager, K);
+		assert_eq!(control_manager.actions.len(), 1);
+		let move_up = Bound {
+			command: GameCommand::MoveUp,
+			binding: Input::Key(W).into(),
+		};
+		assert_eq!(
+			control_manager.drain_rebind_events(),
+			vec![
+				RebindEvent::Started,
+				RebindEvent::Chosen(move_up),
+				RebindEvent::Failed(Conflict {
+					binding: Input::Key(S).into(),
+					commands: (GameCommand::MoveDown, GameCommand::MoveUp),
+				}),
+				RebindEvent::Started,
+				RebindEvent::Chosen(move_up),
+				RebindEvent::Rebound {
+					command: GameCommand::MoveUp,
+					from: Input::Key(W).into(),
+					to: Input::Key(K).into(),
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn held_key_repeats_ignored() {
+		let mut control_manager = ControlManager::with_input_map(InputMap::default());
+		let press = |control_manager: &mut ControlManager| {
+			control_manager.press(F1, Modifiers::default(), (0, 0).into())
+		};
+		press(&mut control_manager);
+		press(&mut control_manager); // Would otherwise choose F1 itself to rebind
+		assert_eq!(
+			control_manager.drain_rebind_events(),
+			vec![RebindEvent::Started]
+		);
+		control_manager.release_all();
+		press(&mut control_manager);
+		assert_eq!(
+			control_manager.drain_rebind_events(),
+			vec![RebindEvent::Chosen(Bound {
+				command: GameCommand::Rebind,
+				binding: Input::Key(F1).into(),
+			})]
+		);
+	}
+
+	fn move_to(x: i64, y: i64) -> Action {
+		Action::MoveTo((x, y).into())
+	}
+
+	fn queued(control_manager: &ControlManager) -> Vec<Action> {
+		control_manager
+			.actions
+			.iter()
+			.map(|player_action| player_action.action.clone())
+			.collect()
+	}
+
+	#[test]
+	fn queue_order() {
+		let mut control_manager = ControlManager::with_input_map(InputMap::default());
+		control_manager.enqueue(move_to(1, 0));
+		control_manager.enqueue(move_to(2, 0));
+		control_manager.start_current();
+		control_manager.start_current(); // Only starts once
+		assert_eq!(
+			control_manager.complete_current().unwrap().action,
+			move_to(1, 0)
+		);
+		control_manager.start_current();
+		assert_eq!(
+			control_manager.complete_current().unwrap().action,
+			move_to(2, 0)
+		);
+		assert!(control_manager.complete_current().is_none());
+		assert_eq!(
+			control_manager.drain_events(),
+			vec![
+				ActionEvent::Started(move_to(1, 0)),
+				ActionEvent::Completed(move_to(1, 0)),
+				ActionEvent::Started(move_to(2, 0)),
+				ActionEvent::Completed(move_to(2, 0)),
+			]
+		);
+	}
+
+	#[test]
+	fn commands_queue_or_replace() {
+		let mut control_manager = ControlManager::with_input_map(InputMap::default());
+		control_manager.command(GameCommand::MoveToCursor, (5, 5).into());
+		control_manager.command(GameCommand::QueueMoveToCursor, (6, 5).into());
+		assert_eq!(queued(&control_manager), vec![move_to(5, 5), move_to(6, 5)]);
+		control_manager.command(GameCommand::MoveRight, (5, 5).into());
+		assert_eq!(
+			queued(&control_manager),
+			vec![Action::Walk {
+				first_step: Some((1, 0).into())
+			}]
+		);
+	}
+
+	#[test]
+	fn cancel() {
+		let mut control_manager = ControlManager::with_input_map(InputMap::default());
+		control_manager.enqueue(move_to(1, 0));
+		control_manager.enqueue(move_to(2, 0));
+		control_manager.start_current();
+		control_manager.cancel_current();
+		assert_eq!(queued(&control_manager), vec![move_to(2, 0)]);
+		assert_eq!(
+			control_manager.drain_events(),
+			vec![
+				ActionEvent::Started(move_to(1, 0)),
+				ActionEvent::Failed(move_to(1, 0), ActionFailure::Cancelled),
+			]
+		);
+	}
+
+	#[test]
+	fn clear() {
+		let mut control_manager = ControlManager::with_input_map(InputMap::default());
+		control_manager.enqueue(move_to(1, 0));
+		control_manager.enqueue(move_to(2, 0));
+		control_manager.replace(move_to(3, 0));
+		assert_eq!(queued(&control_manager), vec![move_to(3, 0)]);
+		control_manager.clear();
+		assert!(control_manager.actions.is_empty());
+		// Everything dropped is reported in queue order
+		assert_eq!(
+			control_manager.drain_events(),
+			vec![
+				ActionEvent::Failed(move_to(1, 0), ActionFailure::Cancelled),
+				ActionEvent::Failed(move_to(2, 0), ActionFailure::Cancelled),
+				ActionEvent::Failed(move_to(3, 0), ActionFailure::Cancelled),
+			]
+		);
+	}
+
+	#[test]
+	fn fail() {
+		let mut control_manager = ControlManager::with_input_map(InputMap::default());
+		assert!(control_manager
+			.fail_current(ActionFailure::Cancelled)
+			.is_none());
+		control_manager.enqueue(move_to(1, 0));
+		control_manager.enqueue(move_to(2, 0));
+		control_manager.start_current();
+		assert_eq!(
+			control_manager
+				.fail_current(ActionFailure::Cancelled)
+				.unwrap()
+				.action,
+			move_to(1, 0)
+		);
+		control_manager.start_current(); // The next action follows on
+		assert_eq!(
+			control_manager.drain_events(),
+			vec![
+				ActionEvent::Started(move_to(1, 0)),
+				ActionEvent::Failed(move_to(1, 0), ActionFailure::Cancelled),
+				ActionEvent::Started(move_to(2, 0)),
+			]
+		);
+	}
+}
@@ -0,0 +1,132 @@
+use {
+	crate::{
+		atlas::{
+			AtlasError,
+			TextureType,
+		},
+		world::{
+			layer::Depth,
+			tile::PixelPos,
+		},
+	},
+	std::{
+		backtrace::Backtrace,
+		env,
+		fmt,
+		fs,
+		io,
+		panic,
+		path::PathBuf,
+		thread,
+		time::{
+			SystemTime,
+			UNIX_EPOCH,
+		},
+	},
+};
+
+#[derive(Debug)]
+pub enum Error {
+	MissingTexture(TextureType),
+	Atlas(AtlasError),
+	Window(String),  // Couldn't open the window or its GL context
+	Present(String), // Couldn't show a finished frame
+	Generation { depth: Depth, chunk: (i64, i64) }, // No ground or resource fits somewhere in the chunk
+	CoordinateOverflow(PixelPos),                   // Too far from the origin to draw
+	Upload(String),                                 // Couldn't make a texture to draw
+	ExportMap { path: PathBuf, error: String },
+	EventLoopMissing,
+	SpawnTickThread(String),
+	TickThreadStopped,
+}
+impl Error {
+	// Recoverable errors only lose part of a frame or the world, the game carries on
+	pub fn is_recoverable(&self) -> bool {
+		use Error::*;
+		matches!(
+			self,
+			MissingTexture(..) | Generation { .. } | CoordinateOverflow(..) | Upload(..)
+		)
+	}
+}
+impl From<AtlasError> for Error {
+	fn from(from: AtlasError) -> Self {
+		Error::Atlas(from)
+	}
+}
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use Error::*;
+		match self {
+			MissingTexture(texture) => write!(f, "No texture for {:?} in the atlas", texture),
+			Atlas(error) => write!(f, "Atlas: {}", error),
+			Window(error) => write!(f, "Could not open window: {}", error),
+			Present(error) => write!(f, "Could not present frame: {}", error),
+			Generation { depth, chunk } => {
+				write!(f, "Could not generate chunk {:?} at depth {}", chunk, depth)
+			}
+			CoordinateOverflow(pos) => write!(f, "Pixel position {:?} is out of range", pos),
+			Upload(error) => write!(f, "Could not upload a texture: {}", error),
+			ExportMap { path, error } => write!(f, "Could not export map {:?}: {}", path, error),
+			EventLoopMissing => write!(f, "Tried to run without the event loop"),
+			SpawnTickThread(error) => write!(f, "Could not start the tick thread: {}", error),
+			TickThreadStopped => write!(f, "The tick thread stopped"),
+		}
+	}
+}
+
+fn crash_dir() -> PathBuf {
+	env::var_os("XDG_DATA_HOME")
+		.map(PathBuf::from)
+		.or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+		.unwrap_or_default()
+		.join("ky_space/crashes")
+}
+
+// Written for anything that ends the game, returns where the report went
+pub fn write_crash_report(error: &str, backtrace: &Backtrace) -> io::Result<PathBuf> {
+	let time = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_secs();
+	let dir = crash_dir();
+	fs::create_dir_all(&dir)?;
+	let path = dir.join(format!("crash-{}.txt", time));
+	let report = format!(
+		"KySpace {} crashed at {} (unix time)\nThread: {}\nOS: {} {}\n\n{}\n\nBacktrace:\n{}\n",
+		env!("CARGO_PKG_VERSION"),
+		time,
+		thread::current().name().unwrap_or("unnamed"),
+		env::consts::OS,
+		env::consts::ARCH,
+		error,
+		backtrace
+	);
+	fs::write(&path, report)?;
+	Ok(path)
+}
+
+fn report_crash(error: &str) {
+	match write_crash_report(error, &Backtrace::force_capture()) {
+		Ok(path) => eprintln!("Crash report written to {:?}", path),
+		Err(report_error) => eprintln!("Could not write crash report: {}", report_error),
+	}
+}
+
+// Panics anywhere, including inside dependencies, also leave a report
+pub fn install_panic_hook() {
+	let default_hook = panic::take_hook();
+	panic::set_hook(Box::new(move |info| {
+		report_crash(&info.to_string());
+		default_hook(info);
+	}));
+}
+
+// For errors the game can't carry on from
+pub fn crash(error: Error) -> ! {
+	eprintln!("{}", error);
+	if !matches!(error, Error::TickThreadStopped) {
+		report_crash(&format!("{}\n{:?}", error, error));
+	} // Otherwise the tick thread's panic was already reported
+	std::process::exit(1)
+}
@@ -0,0 +1,138 @@
+use {
+	crate::{
+		control_manager::{
+			ActionEvent,
+			RebindEvent,
+		},
+		tile::{
+			GroundType,
+			ResourceType,
+		},
+		world::{
+			clock::Phase,
+			layer::Depth,
+			season::Season,
+			tile::TilePos,
+			weather::Weather,
+		},
+	},
+	std::{
+		collections::VecDeque,
+		sync::{
+			mpsc::{
+				self,
+				Receiver,
+				Sender,
+			},
+			Arc,
+		},
+	},
+};
+
+const HISTORY_TICKS: usize = 600; // :. 1 minute
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+	PlayerMoved {
+		from: TilePos,
+		to: TilePos,
+	},
+	PlayerStopped {
+		at: TilePos,
+	},
+	Action(ActionEvent),
+	Rebind(RebindEvent),
+	ChunkGenerated {
+		depth: Depth,
+		chunk: (i64, i64),
+	},
+	TileChanged {
+		depth: Depth,
+		pos: TilePos,
+		ground: GroundType,
+		resource: ResourceType,
+	},
+	PhaseChanged {
+		day: u64,
+		phase: Phase,
+	},
+	SeasonChanged {
+		season: Season,
+	},
+	Harvested {
+		pos: TilePos,
+		resource: ResourceType,
+		amount: u32,
+	},
+	WeatherChanged {
+		region: (i64, i64), // Where the player is
+		weather: Weather,
+	},
+	DepthChanged {
+		depth: Depth,
+	},
+	WaypointToggled {
+		depth: Depth,
+		pos: TilePos,
+		placed: bool, // Otherwise removed
+	},
+	Paused,
+	Resumed,
+}
+
+// Everything that happened during one tick, in order
+#[derive(Clone, Debug, PartialEq)]
+pub struct TickEvents {
+	pub tick: u64,
+	pub events: Vec<GameEvent>,
+}
+
+type Handler = Box<dyn FnMut(&TickEvents) + Send>;
+
+// Fans each tick's events out to subscribers and keeps recent ticks
+#[derive(Default)]
+pub struct EventBus {
+	subscribers: Vec<Sender<Arc<TickEvents>>>,
+	handlers: Vec<Handler>,
+	history: VecDeque<Arc<TickEvents>>, // Oldest first
+}
+impl EventBus {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	// For other threads, the subscription ends when the receiver is dropped
+	pub fn subscribe(&mut self) -> Receiver<Arc<TickEvents>> {
+		let (sender, receiver) = mpsc::channel();
+		self.subscribers.push(sender);
+		receiver
+	}
+	// Called on the tick thread as events are published
+	pub fn on(&mut self, handler: impl FnMut(&TickEvents) + Send + 'static) {
+		self.handlers.push(Box::new(handler));
+	}
+	pub fn publish(&mut self, tick: u64, events: Vec<GameEvent>) {
+		if events.is_empty() {
+			return;
+		}
+		let tick_events = Arc::new(TickEvents { tick, events });
+		self.handlers
+			.iter_mut()
+			.for_each(|handler| handler(&tick_events));
+		self.subscribers
+			.retain(|subscriber| subscriber.send(tick_events.clone()).is_ok());
+
+		self.history.push_back(tick_events);
+		if self.history.len() > HISTORY_TICKS {
+			self.history.pop_front();
+		}
+	}
+	pub fn history(&self) -> impl Iterator<Item = &TickEvents> {
+		self.history.iter().map(|tick_events| tick_events.as_ref())
+	}
+	// Published for `tick`, events from outside ticks are recorded against the tick before
+	pub fn events_at(&self, tick: u64) -> impl Iterator<Item = &GameEvent> {
+		self.history()
+			.filter(move |tick_events| tick_events.tick == tick)
+			.flat_map(|tick_events| tick_events.events.iter())
+	}
+}
@@ -1,16 +1,24 @@
 use {
 	crate::{
-		atlas::Atlas,
+		atlas::{
+			Atlas,
+			TextureType,
+		},
 		world::tile::PixelPos,
 		Error,
 	},
 	crow::{
 		Context,
+		DrawConfig,
 		WindowSurface,
 	},
 };
 
 pub trait Draw {
+	fn texture_type(&self) -> Option<TextureType>; // None draws nothing
+	fn scale(&self) -> u32 {
+		4
+	}
 	fn draw(
 		&self,
 		ctx: &mut Context,
@@ -18,25 +26,28 @@ pub trait Draw {
 		pos: PixelPos,
 		atlas: &Atlas,
 		frame: usize,
+		tint: [[f32; 4]; 4], // Colour modulation, for lighting
 	) -> Result<(), Error> {
-		self.draw_animated(ctx, surface, pos, atlas, frame)
-			.or_else(|_| self.draw_still(ctx, surface, pos, atlas))
+		let texture_type = match self.texture_type() {
+			Some(texture_type) => texture_type,
+			None => return Ok(()),
+		};
+		let texture = atlas
+			.atlas
+			.get(&texture_type)
+			.and_then(|texture| texture.texture_at(frame))
+			.ok_or(Error::MissingTexture(texture_type))?;
+		let scale = self.scale();
+		ctx.draw(
+			surface,
+			texture,
+			pos.try_into()?,
+			&DrawConfig {
+				scale: (scale, scale),
+				color_modulation: tint,
+				..DrawConfig::default()
+			},
+		);
+		Ok(())
 	}
-	fn draw_animated(
-		&self,
-		_ctx: &mut Context,
-		_surface: &mut WindowSurface,
-		_pos: PixelPos,
-		_atlas: &Atlas,
-		_frame: usize,
-	) -> Result<(), Error> {
-		Err(Error::AnimationUnimplemented)
-	}
-	fn draw_still(
-		&self,
-		ctx: &mut Context,
-		surface: &mut WindowSurface,
-		pos: PixelPos,
-		atlas: &Atlas,
-	) -> Result<(), Error>;
 }
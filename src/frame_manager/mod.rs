@@ -1,14 +1,24 @@
 use {
+	self::tooltip::Tooltip,
 	crate::{
-		atlas::Atlas,
-		control_manager::ControlManager,
+		atlas::{
+			AssetWatcher,
+			Atlas,
+		},
+		control_manager::ControlInput,
+		gamepad::Gamepad,
+		input_map::Modifiers,
 		normalise_to,
-		world::tile::{
-			PixelPos,
-			TilePos,
+		tick_manager::TickInput,
+		world::{
+			map::MapTexture,
+			tile::{
+				PixelPos,
+				TilePos,
+			},
+			WorldSnapshot,
 		},
 		Error,
-		World,
 		FRAME_LEN,
 		TILE_SIZE,
 	},
@@ -30,9 +40,11 @@ use {
 		Context,
 	},
 	std::{
-		sync::{
-			Arc,
-			Mutex,
+		collections::HashSet,
+		sync::mpsc::{
+			Receiver,
+			Sender,
+			TryRecvError,
 		},
 		thread,
 		time::Instant,
@@ -40,99 +52,199 @@ use {
 };
 
 pub mod draw;
+pub mod tooltip;
 
 pub struct FrameManager {
 	window_size: PhysicalSize<u32>,
 	mouse_position: PixelPos,
-	board_position: TilePos,
+	hovered: Option<TilePos>, // Last sent, None while the cursor is outside the window
+	cursor_inside: bool,
+	modifiers: Modifiers,
 	board_size: TilePos, // Ideally should be differenciated...
 	board_offset: PixelPos,
 	event_loop: Option<EventLoop<()>>,
 	context: Context,
-	pub control_manager: Arc<Mutex<ControlManager>>,
+	inputs: Sender<TickInput>,
+	snapshots: Receiver<WorldSnapshot>,
+	snapshot: Option<WorldSnapshot>, // Latest from the tick thread
 	frame: usize,
 	atlas: Atlas,
-	world: Arc<Mutex<World>>,
+	map_texture: MapTexture, // Of the minimap, or the full map while it's open
+	tooltip: Tooltip,
+	asset_watcher: Option<AssetWatcher>,
+	gamepad: Option<Gamepad>,
+	reported: HashSet<String>, // Recoverable errors already logged
+	closed: bool,
 	last_frame: Instant,
 }
-// TODO: Confirm safety
-unsafe impl Sync for FrameManager {}
-unsafe impl Send for FrameManager {}
 
 impl FrameManager {
-	pub fn new(world: Arc<Mutex<World>>, control_manager: Arc<Mutex<ControlManager>>) -> Self {
+	pub fn new(
+		inputs: Sender<TickInput>,
+		snapshots: Receiver<WorldSnapshot>,
+		watch_assets: bool,
+	) -> Result<Self, Error> {
 		let event_loop = EventLoop::new();
-		let mut context = Context::new(WindowBuilder::new(), &event_loop).unwrap(); // TODO: Error Management
-		let atlas = Atlas::new(&mut context);
-		Self {
+		let mut context = Context::new(WindowBuilder::new(), &event_loop)
+			.map_err(|error| Error::Window(error.to_string()))?;
+		let atlas = Atlas::new(&mut context)?;
+		let asset_watcher = watch_assets.then(|| AssetWatcher::new(&atlas.manifest));
+		Ok(Self {
 			window_size: PhysicalSize {
 				width: 0,
 				height: 0,
 			},
 			mouse_position: (0, 0).into(),
-			board_position: (0, 0).into(),
+			hovered: None,
+			cursor_inside: false,
+			modifiers: Modifiers::default(),
 			board_size: (0, 0).into(),
 			board_offset: (0, 0).into(),
 			context,
 			event_loop: Some(event_loop),
-			control_manager,
+			inputs,
+			snapshots,
+			snapshot: None,
 			atlas,
-			world,
+			map_texture: MapTexture::default(),
+			tooltip: Tooltip::default(),
+			asset_watcher,
+			gamepad: connect_gamepad(),
+			reported: HashSet::new(),
+			closed: false,
 			frame: 0,
 			last_frame: Instant::now(),
+		})
+	}
+	// Recoverable errors are logged, anything else stops the loop and is returned
+	pub fn run_once(&mut self) -> Result<(), Error> {
+		let mut event_loop = self.event_loop.take().ok_or(Error::EventLoopMissing)?;
+		let mut fatal = None;
+		event_loop.run_return(
+			|event: Event<()>, _window_target, control_flow: &mut ControlFlow| {
+				if let Err(error) = self.frame_run(event, control_flow) {
+					if error.is_recoverable() {
+						self.report(error);
+					} else {
+						fatal = Some(error);
+						*control_flow = ControlFlow::Exit;
+					}
+				}
+			},
+		);
+		self.event_loop.replace(event_loop);
+		fatal.map_or(Ok(()), Err)
+	}
+	pub fn is_closed(&self) -> bool {
+		self.closed
+	}
+	// Logs each distinct error once, rather than every frame
+	fn report(&mut self, error: Error) {
+		let message = error.to_string();
+		if !self.reported.contains(&message) {
+			eprintln!("{}", message);
+			self.reported.insert(message);
+		}
+	}
+	fn receive_snapshot(&mut self) -> Result<(), Error> {
+		loop {
+			match self.snapshots.try_recv() {
+				Ok(snapshot) => self.snapshot = Some(snapshot),
+				Err(TryRecvError::Empty) => return Ok(()),
+				Err(TryRecvError::Disconnected) => return Err(Error::TickThreadStopped),
+			}
+		}
+	}
+	fn cursor_tile(&self) -> TilePos {
+		let player_pos = match &self.snapshot {
+			Some(snapshot) => *snapshot.player.get_position(),
+			None => (0, 0).into(),
+		};
+		self.mouse_position
+			.to_rel_tile_pos(self.window_size)
+			.to_world_tile(&player_pos)
+	}
+	fn send(&self, input: TickInput) -> Result<(), Error> {
+		self.inputs
+			.send(input)
+			.map_err(|_| Error::TickThreadStopped)
+	}
+	// The tile under the cursor changes as the player moves too, so this is checked every frame
+	fn update_hover(&mut self) -> Result<(), Error> {
+		let hovered = self.cursor_inside.then(|| self.cursor_tile());
+		if hovered != self.hovered {
+			self.hovered = hovered;
+			self.send(TickInput::Hover { cursor: hovered })?;
 		}
+		Ok(())
 	}
-	pub fn run_once(&mut self) {
-		if let Some(mut event_loop) = self.event_loop.take() {
-			event_loop.run_return(
-				|event: Event<()>, _window_target, control_flow: &mut ControlFlow| {
-					self.frame_run(event, control_flow).unwrap();
-				},
-			);
-			self.event_loop.replace(event_loop);
-		} else {
-			panic!("Tried to run but didn't have access to event loop");
+	fn poll_gamepad(&mut self) -> Result<(), Error> {
+		let faced = match &self.snapshot {
+			Some(snapshot) => snapshot.player.faced(),
+			None => (0, 0).into(),
+		};
+		if let Some(gamepad) = self.gamepad.as_mut() {
+			for input in gamepad.poll(faced) {
+				self.send(TickInput::Control(input))?;
+			}
+		}
+		Ok(())
+	}
+	fn reload_assets(&mut self) {
+		if let Some(watcher) = self.asset_watcher.as_mut() {
+			if let Some(change) = watcher.poll(&self.atlas.manifest) {
+				match self.atlas.reload(&mut self.context) {
+					Ok(()) => {
+						println!("Reloaded assets: {:?}", change);
+						watcher.watch(&self.atlas.manifest);
+					}
+					Err(error) => eprintln!("Keeping old assets, reload failed: {}", error),
+				}
+			}
 		}
 	}
 	fn frame_run(&mut self, event: Event<()>, control_flow: &mut ControlFlow) -> Result<(), Error> {
 		match event {
 			Event::WindowEvent { event, .. } => match event {
-				WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+				WindowEvent::CloseRequested => {
+					self.closed = true;
+					*control_flow = ControlFlow::Exit;
+				}
 				WindowEvent::Resized(new_size) => self.window_size = new_size,
 				WindowEvent::CursorMoved { position, .. } => {
 					self.mouse_position =
 						PixelPos::from_mouse(position, self.context.window().inner_size());
+					self.cursor_inside = true;
 				}
+				WindowEvent::CursorEntered { .. } => self.cursor_inside = true,
+				WindowEvent::CursorLeft { .. } => self.cursor_inside = false,
+				WindowEvent::ModifiersChanged(modifiers) => self.modifiers = modifiers.into(),
 				WindowEvent::MouseInput {
 					state: ElementState::Pressed,
 					button,
 					..
-				} => {
-					let clicked = self
-						.mouse_position
-						.to_rel_tile_pos(self.window_size)
-						.to_world_tile(self.world.lock().unwrap().player.get_position());
-
-					if let Ok(mut control_manager) = self.control_manager.lock() {
-						(*control_manager).click(button, clicked);
-					} else {
-						return Err(Error::ControlManagerLocked);
-					}
-				}
+				} => self.send(TickInput::Control(ControlInput::Click {
+					button,
+					modifiers: self.modifiers,
+					cursor: self.cursor_tile(),
+				}))?,
 				WindowEvent::KeyboardInput { input, .. } => {
-					if input.state == ElementState::Pressed {
-						if let Some(keycode) = input.virtual_keycode {
-							if let Ok(mut control_manager) = self.control_manager.lock() {
-								(*control_manager).press(
-									keycode,
-									*self.world.lock().unwrap().player.get_position(),
-								);
-							} else {
-								return Err(Error::ControlManagerLocked);
-							}
-						}
+					if let Some(keycode) = input.virtual_keycode {
+						let input = match input.state {
+							ElementState::Pressed => ControlInput::Press {
+								keycode,
+								modifiers: self.modifiers,
+								cursor: self.cursor_tile(),
+							},
+							ElementState::Released => ControlInput::Release { keycode },
+						};
+						self.send(TickInput::Control(input))?;
 					}
 				}
+				WindowEvent::Focused(false) => {
+					// Releases are missed while unfocused
+					self.send(TickInput::Control(ControlInput::ReleaseAll))?;
+				}
 				_ => (),
 			},
 			Event::MainEventsCleared => self.context.window().request_redraw(),
@@ -143,40 +255,88 @@ impl FrameManager {
 				));
 				self.last_frame = Instant::now();
 				self.frame += 1;
+				self.reload_assets();
+				self.poll_gamepad()?;
+				self.update_hover()?;
 
 				let mut surface = self.context.surface();
 				self.context.clear_color(&mut surface, (0.0, 0.0, 0.0, 1.0));
 
 				let window_size = self.context.window().inner_size();
 
-				self.board_size = window_size.into();
+				let board_size = window_size.into();
+				if board_size != self.board_size {
+					self.board_size = board_size;
+					self.send(TickInput::Resize { size: board_size })?;
+				}
 				self.board_offset = (
 					(window_size.width as i64 - (self.board_size.x * TILE_SIZE)) / 2,
 					(window_size.height as i64 - (self.board_size.y * TILE_SIZE)) / 2,
 				)
 					.into(); // Get difference halved TODO: Use std ops
 
-				if let Ok(mut world) = self.world.lock() {
-					let player_pos = world.player.get_position();
-					self.board_position = *player_pos - &(self.board_size / 4); // TODO: Define differently // I have no idea why this is 4 and not 2
-					(*world).load(self.board_position, self.board_size);
-
-					world.draw(
+				self.receive_snapshot()?;
+				if let Some(snapshot) = &self.snapshot {
+					let mut errors = snapshot.draw(
 						&mut self.context,
 						&mut surface,
 						&self.atlas,
-						self.board_position,
-						self.board_size,
 						self.board_offset,
 						self.frame,
-					)?;
-				} else {
-					return Err(Error::ControlManagerLocked);
+					);
+					errors.extend(
+						snapshot
+							.draw_map(
+								&mut self.context,
+								&mut surface,
+								&mut self.map_texture,
+								window_size.into(),
+							)
+							.err(),
+					);
+					if let (Some(info), None) = (&snapshot.hovered, &snapshot.map) {
+						errors.extend(
+							self.tooltip
+								.draw(
+									&mut self.context,
+									&mut surface,
+									info,
+									self.mouse_position,
+									window_size.into(),
+								)
+								.err(),
+						);
+					}
+					errors.into_iter().for_each(|error| self.report(error));
 				}
-				self.context.present(surface).unwrap();
+				self.context
+					.present(surface)
+					.map_err(|error| Error::Present(error.to_string()))?;
 			}
 			_ => (),
 		}
 		Ok(())
 	}
 }
+
+#[cfg(feature = "gamepad")]
+fn connect_gamepad() -> Option<Gamepad> {
+	use crate::gamepad::{
+		GamepadConfig,
+		GilrsBackend,
+	};
+	match GilrsBackend::new() {
+		Ok(backend) => Some(Gamepad::new(
+			GamepadConfig::load_or_default(),
+			Box::new(backend),
+		)),
+		Err(error) => {
+			eprintln!("Playing without gamepad: {}", error);
+			None
+		}
+	}
+}
+#[cfg(not(feature = "gamepad"))]
+fn connect_gamepad() -> Option<Gamepad> {
+	None // Built without a gamepad backend
+}
@@ -0,0 +1,260 @@
+use {
+	crate::{
+		world::{
+			inspect::{
+				Entity,
+				TileInfo,
+			},
+			layer::SURFACE,
+			tile::PixelPos,
+		},
+		Error,
+	},
+	crow::{
+		image::{
+			Rgba,
+			RgbaImage,
+		},
+		Context,
+		DrawConfig,
+		Texture,
+		WindowSurface,
+	},
+};
+
+const SCALE: u32 = 3; // Screen pixels per font pixel
+const GLYPH: (u32, u32) = (3, 5); // Font pixels
+const GAP: u32 = 1; // Between letters and lines
+const PADDING: u32 = 2;
+const OFFSET: i64 = 16; // Screen pixels from the cursor
+const TEXT: Rgba<u8> = Rgba([240, 240, 240, 255]);
+const BACKGROUND: Rgba<u8> = Rgba([16, 16, 20, 220]);
+
+// Rows top to bottom, the low 3 bits of each are the pixels left to right
+fn glyph(letter: char) -> [u8; 5] {
+	match letter.to_ascii_uppercase() {
+		'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+		'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+		'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+		'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+		'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+		'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+		'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+		'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+		'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+		'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+		'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+		'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+		'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+		'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+		'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+		'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+		'Q' => [0b010, 0b101, 0b101, 0b111, 0b011],
+		'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+		'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+		'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+		'U' => [0b101, 0b101, 0b101, 0b101, 0b011],
+		'V' => [0b101, 0b101, 0b101, 0b010, 0b010],
+		'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+		'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+		'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+		'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+		'0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+		'1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+		'2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+		'3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+		'4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+		'5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+		'6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+		'7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+		'8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+		'9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+		'-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+		':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+		',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+		'(' => [0b001, 0b010, 0b010, 0b010, 0b001],
+		')' => [0b100, 0b010, 0b010, 0b010, 0b100],
+		_ => [0; 5], // Spaces, and anything without a glyph
+	}
+}
+
+// Splits debug names into words, e.g. WindSweptGrass into Wind Swept Grass
+fn words(name: impl std::fmt::Debug) -> String {
+	let name = format!("{:?}", name);
+	let mut words = String::new();
+	for (index, letter) in name.chars().enumerate() {
+		if index > 0 && letter.is_ascii_uppercase() {
+			words.push(' ');
+		}
+		words.push(letter);
+	}
+	words
+}
+
+// Unexplored tiles keep what's there to themselves
+pub fn lines(info: &TileInfo) -> Vec<String> {
+	let mut lines = vec![if info.depth == SURFACE {
+		format!("({}, {})", info.pos.x, info.pos.y)
+	} else {
+		format!("({}, {}) depth {}", info.pos.x, info.pos.y, info.depth)
+	}];
+	if !info.explored {
+		lines.push("Unexplored".into());
+		return lines;
+	}
+	match &info.tile {
+		Some((ground, resource)) => {
+			lines.push(format!("Ground: {}", words(ground)));
+			lines.push(format!("Resource: {}", words(resource)));
+		}
+		None => lines.push("Not generated".into()),
+	}
+	lines.push(if info.walkable { "Walkable" } else { "Blocked" }.into());
+	lines.extend(info.entities.iter().map(|entity| match entity {
+		Entity::Structure(kind) => format!("Structure: {}", words(kind)),
+		entity => words(entity),
+	}));
+	lines
+}
+
+// Light text on a dark box, in font pixels
+fn render(lines: &[String]) -> RgbaImage {
+	let longest = lines
+		.iter()
+		.map(|line| line.chars().count())
+		.max()
+		.unwrap_or(0) as u32;
+	let (glyph_x, glyph_y) = (GLYPH.0 + GAP, GLYPH.1 + GAP);
+	let mut image = RgbaImage::from_pixel(
+		longest * glyph_x - GAP + PADDING * 2,
+		lines.len() as u32 * glyph_y - GAP + PADDING * 2,
+		BACKGROUND,
+	);
+	for (row, line) in lines.iter().enumerate() {
+		for (column, letter) in line.chars().enumerate() {
+			let corner = (
+				PADDING + column as u32 * glyph_x,
+				PADDING + row as u32 * glyph_y,
+			);
+			for (y, bits) in glyph(letter).iter().enumerate() {
+				for x in 0..GLYPH.0 {
+					if bits & (0b100 >> x) != 0 {
+						image.put_pixel(corner.0 + x, corner.1 + y as u32, TEXT);
+					}
+				}
+			}
+		}
+	}
+	image
+}
+
+// The last tooltip drawn, only rendered and uploaded again once its text changes
+#[derive(Default)]
+pub struct Tooltip(Option<(Vec<String>, Texture)>);
+impl Tooltip {
+	// Below and right of the cursor, kept on screen
+	pub fn draw(
+		&mut self,
+		ctx: &mut Context,
+		surface: &mut WindowSurface,
+		info: &TileInfo,
+		cursor: PixelPos,
+		window_size: PixelPos,
+	) -> Result<(), Error> {
+		let lines = lines(info);
+		let texture = match &self.0 {
+			Some((drawn, texture)) if *drawn == lines => texture,
+			_ => {
+				let texture = Texture::from_image(ctx, render(&lines))
+					.map_err(|error| Error::Upload(error.to_string()))?;
+				&self.0.insert((lines, texture)).1
+			}
+		};
+		let size = PixelPos::from((
+			i64::from(texture.width() * SCALE),
+			i64::from(texture.height() * SCALE),
+		));
+		let pos = PixelPos::from((
+			(cursor.x + OFFSET).min(window_size.x - size.x).max(0),
+			(cursor.y - OFFSET - size.y).max(0),
+		));
+		ctx.draw(
+			surface,
+			texture,
+			pos.try_into()?,
+			&DrawConfig {
+				scale: (SCALE, SCALE),
+				..DrawConfig::default()
+			},
+		);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		crate::{
+			tile::{
+				GroundType,
+				ResourceType,
+			},
+			world::{
+				layer::CAVES,
+				structures::StructureKind,
+			},
+		},
+	};
+
+	fn info() -> TileInfo {
+		TileInfo {
+			pos: (3, -2).into(),
+			depth: SURFACE,
+			explored: true,
+			tile: Some((GroundType::WindSweptGrass, ResourceType::Tree)),
+			walkable: false,
+			entities: vec![Entity::Player, Entity::Waypoint],
+		}
+	}
+
+	#[test]
+	fn explored() {
+		assert_eq!(
+			lines(&info()),
+			[
+				"(3, -2)",
+				"Ground: Wind Swept Grass",
+				"Resource: Tree",
+				"Blocked",
+				"Player",
+				"Waypoint"
+			]
+		);
+		let info = TileInfo {
+			depth: CAVES,
+			tile: None,
+			walkable: true,
+			entities: vec![Entity::Structure(StructureKind::CaveEntrance)],
+			..info()
+		};
+		assert_eq!(
+			lines(&info),
+			[
+				"(3, -2) depth 1",
+				"Not generated",
+				"Walkable",
+				"Structure: Cave Entrance"
+			]
+		);
+	}
+
+	#[test]
+	fn unexplored() {
+		let info = TileInfo {
+			explored: false,
+			..info()
+		};
+		assert_eq!(lines(&info), ["(3, -2)", "Unexplored"]);
+	}
+}
@@ -0,0 +1,69 @@
+use {
+	super::{
+		GamepadAxis,
+		GamepadBackend,
+		GamepadButton,
+		GamepadEvent,
+	},
+	gilrs::{
+		Axis,
+		Button,
+		EventType,
+		Gilrs,
+	},
+};
+
+pub struct GilrsBackend {
+	gilrs: Gilrs,
+}
+impl GilrsBackend {
+	pub fn new() -> Result<Self, String> {
+		Gilrs::new()
+			.map(|gilrs| Self { gilrs })
+			.map_err(|error| error.to_string())
+	}
+}
+impl GamepadBackend for GilrsBackend {
+	fn poll(&mut self) -> Vec<GamepadEvent> {
+		let mut events = vec![];
+		while let Some(event) = self.gilrs.next_event() {
+			let event = match event.event {
+				EventType::ButtonPressed(button, _) => {
+					button_from(button).map(GamepadEvent::ButtonPressed)
+				}
+				EventType::ButtonReleased(button, _) => {
+					button_from(button).map(GamepadEvent::ButtonReleased)
+				}
+				EventType::AxisChanged(Axis::LeftStickX, value, _) => {
+					Some(GamepadEvent::AxisMoved(GamepadAxis::LeftStickX, value))
+				}
+				EventType::AxisChanged(Axis::LeftStickY, value, _) => {
+					Some(GamepadEvent::AxisMoved(GamepadAxis::LeftStickY, value))
+				}
+				EventType::Disconnected => Some(GamepadEvent::Disconnected),
+				_ => None,
+			};
+			events.extend(event);
+		}
+		events
+	}
+}
+
+fn button_from(button: Button) -> Option<GamepadButton> {
+	use GamepadButton::*;
+	Some(match button {
+		Button::South => South,
+		Button::East => East,
+		Button::North => North,
+		Button::West => West,
+		Button::LeftTrigger => LeftBumper,
+		Button::RightTrigger => RightBumper,
+		Button::Start => Start,
+		Button::Select => Select,
+		Button::DPadUp => DPadUp,
+		Button::DPadDown => DPadDown,
+		Button::DPadLeft => DPadLeft,
+		Button::DPadRight => DPadRight,
+		_ => return None,
+	})
+}
@@ -0,0 +1,325 @@
+use {
+	crate::{
+		control_manager::ControlInput,
+		input_map::{
+			config_dir,
+			GameCommand,
+		},
+		world::tile::TilePos,
+	},
+	serde::{
+		Deserialize,
+		Serialize,
+	},
+	std::{
+		collections::{
+			HashSet,
+			VecDeque,
+		},
+		fs,
+		path::{
+			Path,
+			PathBuf,
+		},
+	},
+};
+
+#[cfg(feature = "gamepad")]
+mod gilrs_backend;
+#[cfg(feature = "gamepad")]
+pub use gilrs_backend::GilrsBackend;
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GamepadButton {
+	South,
+	East,
+	North,
+	West,
+	LeftBumper,
+	RightBumper,
+	Start,
+	Select,
+	DPadUp,
+	DPadDown,
+	DPadLeft,
+	DPadRight,
+}
+impl GamepadButton {
+	fn direction(&self) -> Option<TilePos> {
+		use GamepadButton::*;
+		match self {
+			DPadUp => Some((0, 1).into()),
+			DPadDown => Some((0, -1).into()),
+			DPadLeft => Some((-1, 0).into()),
+			DPadRight => Some((1, 0).into()),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GamepadAxis {
+	LeftStickX,
+	LeftStickY, // Up is positive
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GamepadEvent {
+	ButtonPressed(GamepadButton),
+	ButtonReleased(GamepadButton),
+	AxisMoved(GamepadAxis, f32),
+	Disconnected,
+}
+
+pub trait GamepadBackend {
+	fn poll(&mut self) -> Vec<GamepadEvent>;
+}
+
+// Feeds queued events, for driving the gamepad layer without hardware
+#[derive(Default)]
+pub struct SyntheticGamepad {
+	events: VecDeque<GamepadEvent>,
+}
+impl SyntheticGamepad {
+	pub fn inject(&mut self, event: GamepadEvent) {
+		self.events.push_back(event);
+	}
+}
+impl GamepadBackend for SyntheticGamepad {
+	fn poll(&mut self) -> Vec<GamepadEvent> {
+		self.events.drain(..).collect()
+	}
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ButtonBinding {
+	pub button: GamepadButton,
+	pub command: GameCommand,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadConfig {
+	pub dead_zone: f32, // Stick deflection ignored, out of 1.0
+	pub buttons: Vec<ButtonBinding>,
+}
+impl Default for GamepadConfig {
+	fn default() -> Self {
+		use {
+			GameCommand::*,
+			GamepadButton::*,
+		};
+		Self {
+			dead_zone: 0.3,
+			buttons: [
+				(South, Interact),
+				(West, Attack),
+				(North, Inventory),
+				(East, CancelAction),
+				(Select, ClearActions),
+				(Start, TogglePause),
+			]
+			.into_iter()
+			.map(|(button, command)| ButtonBinding { button, command })
+			.collect(),
+		}
+	}
+}
+impl GamepadConfig {
+	pub fn config_path() -> PathBuf {
+		config_dir().join("gamepad.toml")
+	}
+	pub fn load_or_default() -> Self {
+		let path = Self::config_path();
+		if !path.exists() {
+			return Self::default();
+		}
+		Self::load(&path).unwrap_or_else(|error| {
+			eprintln!("Using default gamepad config: {}", error);
+			Self::default()
+		})
+	}
+	pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+		let path = path.as_ref();
+		let source = fs::read_to_string(path)
+			.map_err(|error| format!("could not read {:?}: {}", path, error))?;
+		toml::from_str(&source).map_err(|error| format!("could not parse {:?}: {}", path, error))
+	}
+	pub fn command(&self, button: GamepadButton) -> Option<GameCommand> {
+		self.buttons
+			.iter()
+			.find(|binding| binding.button == button)
+			.map(|binding| binding.command)
+	}
+}
+
+pub struct Gamepad {
+	pub config: GamepadConfig,
+	backend: Box<dyn GamepadBackend>,
+	stick: (f32, f32),
+	dpad: HashSet<GamepadButton>,
+	sent_direction: TilePos, // Only changes are sent on
+}
+impl Gamepad {
+	pub fn new(config: GamepadConfig, backend: Box<dyn GamepadBackend>) -> Self {
+		Self {
+			config,
+			backend,
+			stick: (0.0, 0.0),
+			dpad: HashSet::new(),
+			sent_direction: (0, 0).into(),
+		}
+	}
+	// Turns backend events into input for the controls. A gamepad has no cursor, so commands act on
+	// `target`, the tile the player faces.
+	pub fn poll(&mut self, target: TilePos) -> Vec<ControlInput> {
+		self.backend
+			.poll()
+			.into_iter()
+			.filter_map(|event| self.handle(event, target))
+			.collect()
+	}
+	pub fn handle(&mut self, event: GamepadEvent, target: TilePos) -> Option<ControlInput> {
+		match event {
+			GamepadEvent::ButtonPressed(button) if button.direction().is_some() => {
+				self.dpad.insert(button);
+			}
+			GamepadEvent::ButtonPressed(button) => {
+				let command = self.config.command(button)?;
+				return Some(ControlInput::Command {
+					command,
+					cursor: target,
+				});
+			}
+			GamepadEvent::ButtonReleased(button) => {
+				if !self.dpad.remove(&button) {
+					return None;
+				}
+			}
+			GamepadEvent::AxisMoved(GamepadAxis::LeftStickX, value) => self.stick.0 = value,
+			GamepadEvent::AxisMoved(GamepadAxis::LeftStickY, value) => self.stick.1 = value,
+			GamepadEvent::Disconnected => {
+				self.stick = (0.0, 0.0);
+				self.dpad.clear();
+			}
+		}
+		let direction = self.direction();
+		if direction == self.sent_direction {
+			return None;
+		}
+		self.sent_direction = direction;
+		Some(ControlInput::GamepadDirection { direction })
+	}
+	pub fn direction(&self) -> TilePos {
+		let axis = |value: f32| {
+			if value.abs() > self.config.dead_zone {
+				value.signum() as i64
+			} else {
+				0
+			}
+		};
+		let (x, y) = self.dpad.iter().filter_map(GamepadButton::direction).fold(
+			(axis(self.stick.0), axis(self.stick.1)),
+			|(x, y), direction| (x + direction.x, y + direction.y),
+		);
+		(x.signum(), y.signum()).into()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		GamepadAxis::*,
+		GamepadButton::*,
+		GamepadEvent::*,
+	};
+
+	// Feeds `events` through a synthetic pad, returning what the controls would get
+	fn poll(gamepad: &mut Gamepad, events: &[GamepadEvent]) -> Vec<ControlInput> {
+		let mut backend = SyntheticGamepad::default();
+		events.iter().for_each(|event| backend.inject(*event));
+		gamepad.backend = Box::new(backend);
+		gamepad.poll((5, 5).into())
+	}
+
+	fn gamepad() -> Gamepad {
+		Gamepad::new(
+			GamepadConfig::default(),
+			Box::new(SyntheticGamepad::default()),
+		)
+	}
+
+	fn direction(x: i64, y: i64) -> ControlInput {
+		ControlInput::GamepadDirection {
+			direction: (x, y).into(),
+		}
+	}
+
+	#[test]
+	fn dead_zone() {
+		let mut gamepad = gamepad();
+		assert_eq!(poll(&mut gamepad, &[AxisMoved(LeftStickX, 0.2)]), vec![]);
+		assert_eq!(
+			poll(&mut gamepad, &[AxisMoved(LeftStickX, -0.5)]),
+			vec![direction(-1, 0)]
+		);
+		// Only changes are sent on
+		assert_eq!(poll(&mut gamepad, &[AxisMoved(LeftStickX, -0.9)]), vec![]);
+		assert_eq!(
+			poll(&mut gamepad, &[AxisMoved(LeftStickX, 0.29)]),
+			vec![direction(0, 0)]
+		);
+	}
+
+	#[test]
+	fn stick_and_dpad_combine() {
+		let mut gamepad = gamepad();
+		assert_eq!(
+			poll(
+				&mut gamepad,
+				&[AxisMoved(LeftStickY, 0.8), ButtonPressed(DPadRight)]
+			),
+			vec![direction(0, 1), direction(1, 1)]
+		);
+		// Opposites cancel out
+		assert_eq!(
+			poll(&mut gamepad, &[ButtonPressed(DPadDown)]),
+			vec![direction(1, 0)]
+		);
+		assert_eq!(
+			poll(
+				&mut gamepad,
+				&[ButtonReleased(DPadRight), ButtonReleased(DPadDown)]
+			),
+			vec![direction(0, 0), direction(0, 1)]
+		);
+		assert_eq!(poll(&mut gamepad, &[Disconnected]), vec![direction(0, 0)]);
+	}
+
+	#[test]
+	fn buttons_send_commands() {
+		let mut gamepad = gamepad();
+		assert_eq!(
+			poll(
+				&mut gamepad,
+				&[
+					ButtonPressed(South),
+					ButtonReleased(South),
+					ButtonPressed(LeftBumper), // Unbound
+					ButtonPressed(Start),
+				]
+			),
+			vec![
+				ControlInput::Command {
+					command: GameCommand::Interact,
+					cursor: (5, 5).into(),
+				},
+				ControlInput::Command {
+					command: GameCommand::TogglePause,
+					cursor: (5, 5).into(),
+				},
+			]
+		);
+	}
+}
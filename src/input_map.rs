@@ -0,0 +1,520 @@
+use {
+	crate::world::tile::TilePos,
+	crow::glutin::event::{
+		ModifiersState,
+		MouseButton,
+		VirtualKeyCode,
+	},
+	serde::{
+		Deserialize,
+		Serialize,
+	},
+	std::{
+		env,
+		fmt,
+		fs,
+		path::{
+			Path,
+			PathBuf,
+		},
+	},
+};
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameCommand {
+	MoveUp,
+	MoveDown,
+	MoveLeft,
+	MoveRight,
+	MoveToCursor,
+	QueueMoveToCursor,
+	CancelAction,
+	ClearActions,
+	Interact,
+	Attack,
+	Inventory,
+	TogglePause,
+	Rebind, // The next input picks a binding, the one after replaces it
+	ToggleMap,
+	ToggleWaypoint, // At the cursor
+}
+
+impl GameCommand {
+	pub fn direction(&self) -> Option<TilePos> {
+		use GameCommand::*;
+		match self {
+			MoveUp => Some((0, 1).into()),
+			MoveDown => Some((0, -1).into()),
+			MoveLeft => Some((-1, 0).into()),
+			MoveRight => Some((1, 0).into()),
+			_ => None,
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Input {
+	Key(VirtualKeyCode),
+	Mouse(MouseButton),
+}
+
+// Held alongside other inputs rather than bound themselves
+pub fn is_modifier(keycode: VirtualKeyCode) -> bool {
+	use VirtualKeyCode::*;
+	matches!(
+		keycode,
+		LShift | RShift | LControl | RControl | LAlt | RAlt | LWin | RWin
+	)
+}
+
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Modifiers {
+	pub shift: bool,
+	pub ctrl: bool,
+	pub alt: bool,
+}
+impl From<ModifiersState> for Modifiers {
+	fn from(from: ModifiersState) -> Self {
+		Self {
+			shift: from.shift(),
+			ctrl: from.ctrl(),
+			alt: from.alt(),
+		}
+	}
+}
+
+const SHIFT: Modifiers = Modifiers {
+	shift: true,
+	ctrl: false,
+	alt: false,
+};
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct Binding {
+	pub input: Input,
+	pub modifiers: Modifiers,
+}
+impl From<Input> for Binding {
+	fn from(input: Input) -> Self {
+		Self {
+			input,
+			modifiers: Modifiers::default(),
+		}
+	}
+}
+// As a player would write it, e.g. Shift+Escape
+impl fmt::Display for Binding {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let Modifiers { shift, ctrl, alt } = self.modifiers;
+		for (held, name) in [(ctrl, "Ctrl"), (alt, "Alt"), (shift, "Shift")] {
+			if held {
+				write!(f, "{}+", name)?;
+			}
+		}
+		match self.input {
+			Input::Key(key) => write!(f, "{:?}", key),
+			Input::Mouse(button) => write!(f, "{:?} mouse", button),
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "BoundConfig", try_from = "BoundConfig")]
+pub struct Bound {
+	pub command: GameCommand,
+	pub binding: Binding,
+}
+
+// How a binding is written in the config file, exactly one of `key` and `mouse` is set
+#[derive(Clone, Serialize, Deserialize)]
+struct BoundConfig {
+	command: GameCommand,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	key: Option<VirtualKeyCode>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	mouse: Option<MouseButton>,
+	#[serde(default)]
+	shift: bool,
+	#[serde(default)]
+	ctrl: bool,
+	#[serde(default)]
+	alt: bool,
+}
+impl From<Bound> for BoundConfig {
+	fn from(from: Bound) -> Self {
+		let Bound { command, binding } = from;
+		let (key, mouse) = match binding.input {
+			Input::Key(key) => (Some(key), None),
+			Input::Mouse(button) => (None, Some(button)),
+		};
+		let Modifiers { shift, ctrl, alt } = binding.modifiers;
+		Self {
+			command,
+			key,
+			mouse,
+			shift,
+			ctrl,
+			alt,
+		}
+	}
+}
+impl TryFrom<BoundConfig> for Bound {
+	type Error = String;
+	fn try_from(from: BoundConfig) -> Result<Self, Self::Error> {
+		let input = match (from.key, from.mouse) {
+			(Some(key), None) => Input::Key(key),
+			(None, Some(button)) => Input::Mouse(button),
+			_ => {
+				return Err(format!(
+					"binding for {:?} needs exactly one of `key` and `mouse`",
+					from.command
+				))
+			}
+		};
+		Ok(Self {
+			command: from.command,
+			binding: Binding {
+				input,
+				modifiers: Modifiers {
+					shift: from.shift,
+					ctrl: from.ctrl,
+					alt: from.alt,
+				},
+			},
+		})
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conflict {
+	pub binding: Binding,
+	pub commands: (GameCommand, GameCommand),
+}
+impl fmt::Display for Conflict {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{} is bound to both {:?} and {:?}",
+			self.binding, self.commands.0, self.commands.1
+		)
+	}
+}
+
+#[derive(Debug)]
+pub enum InputMapError {
+	Read { path: PathBuf, error: String },
+	Parse { path: PathBuf, error: String },
+	Write { path: PathBuf, error: String },
+	Conflicts(Vec<Conflict>),
+}
+impl fmt::Display for InputMapError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use InputMapError::*;
+		match self {
+			Read { path, error } => write!(f, "could not read bindings {:?}: {}", path, error),
+			Parse { path, error } => write!(f, "could not parse bindings {:?}: {}", path, error),
+			Write { path, error } => write!(f, "could not write bindings {:?}: {}", path, error),
+			Conflicts(conflicts) => {
+				write!(f, "conflicting bindings:")?;
+				conflicts
+					.iter()
+					.try_for_each(|conflict| write!(f, " {};", conflict))
+			}
+		}
+	}
+}
+
+pub fn config_dir() -> PathBuf {
+	env::var_os("XDG_CONFIG_HOME")
+		.map(PathBuf::from)
+		.or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+		.unwrap_or_default()
+		.join("ky_space")
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputMap {
+	pub bindings: Vec<Bound>,
+}
+impl Default for InputMap {
+	fn default() -> Self {
+		use {
+			GameCommand::*,
+			VirtualKeyCode::*,
+		};
+		let keys = [
+			(MoveRight, Right),
+			(MoveRight, D),
+			(MoveLeft, Left),
+			(MoveLeft, A),
+			(MoveUp, Up),
+			(MoveUp, W),
+			(MoveDown, Down),
+			(MoveDown, S),
+			(Interact, E),
+			(Attack, Space),
+			(Inventory, I),
+			(TogglePause, P),
+			(Rebind, F1),
+			(ToggleMap, M),
+		]
+		.into_iter()
+		.map(|(command, key)| Bound {
+			command,
+			binding: Input::Key(key).into(),
+		});
+		let buttons = [
+			(MoveToCursor, MouseButton::Left),
+			(ToggleWaypoint, MouseButton::Right),
+		]
+		.into_iter()
+		.map(|(command, button)| Bound {
+			command,
+			binding: Input::Mouse(button).into(),
+		});
+		let modified = [
+			(QueueMoveToCursor, Input::Mouse(MouseButton::Left), SHIFT),
+			(CancelAction, Input::Key(Escape), Modifiers::default()),
+			(ClearActions, Input::Key(Escape), SHIFT),
+		]
+		.into_iter()
+		.map(|(command, input, modifiers)| Bound {
+			command,
+			binding: Binding { input, modifiers },
+		});
+		Self {
+			bindings: keys.chain(buttons).chain(modified).collect(),
+		}
+	}
+}
+impl InputMap {
+	pub fn config_path() -> PathBuf {
+		config_dir().join("bindings.toml")
+	}
+	// Falls back to the defaults if there is no config, or it can't be used
+	pub fn load_or_default() -> Self {
+		let path = Self::config_path();
+		if !path.exists() {
+			return Self::default();
+		}
+		Self::load(&path).unwrap_or_else(|error| {
+			eprintln!("Using default bindings: {}", error);
+			Self::default()
+		})
+	}
+	pub fn load(path: impl AsRef<Path>) -> Result<Self, InputMapError> {
+		let path = path.as_ref();
+		let source = fs::read_to_string(path).map_err(|error| InputMapError::Read {
+			path: path.to_path_buf(),
+			error: error.to_string(),
+		})?;
+		let input_map: Self = toml::from_str(&source).map_err(|error| InputMapError::Parse {
+			path: path.to_path_buf(),
+			error: error.to_string(),
+		})?;
+		let conflicts = input_map.conflicts();
+		if conflicts.is_empty() {
+			Ok(input_map)
+		} else {
+			Err(InputMapError::Conflicts(conflicts))
+		}
+	}
+	pub fn save(&self, path: impl AsRef<Path>) -> Result<(), InputMapError> {
+		let path = path.as_ref();
+		let write_error = |error: String| InputMapError::Write {
+			path: path.to_path_buf(),
+			error,
+		};
+		let source = toml::to_string(self).map_err(|error| write_error(error.to_string()))?;
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent).map_err(|error| write_error(error.to_string()))?;
+		}
+		fs::write(path, source).map_err(|error| write_error(error.to_string()))
+	}
+	pub fn conflicts(&self) -> Vec<Conflict> {
+		let mut conflicts = vec![];
+		for (index, bound) in self.bindings.iter().enumerate() {
+			for other in &self.bindings[index + 1..] {
+				if bound.binding == other.binding && bound.command != other.command {
+					conflicts.push(Conflict {
+						binding: bound.binding,
+						commands: (bound.command, other.command),
+					});
+				}
+			}
+		}
+		conflicts
+	}
+	// An exact modifier match wins, otherwise modifiers are ignored so held shift doesn't block movement
+	pub fn bound(&self, input: Input, modifiers: Modifiers) -> Option<Bound> {
+		let find = |binding: Binding| {
+			self.bindings
+				.iter()
+				.find(|bound| bound.binding == binding)
+				.copied()
+		};
+		find(Binding { input, modifiers }).or_else(|| find(input.into()))
+	}
+	pub fn command(&self, input: Input, modifiers: Modifiers) -> Option<GameCommand> {
+		self.bound(input, modifiers).map(|bound| bound.command)
+	}
+	pub fn bindings_for(&self, command: GameCommand) -> impl Iterator<Item = &Binding> {
+		self.bindings
+			.iter()
+			.filter(move |bound| bound.command == command)
+			.map(|bound| &bound.binding)
+	}
+	pub fn bind(&mut self, command: GameCommand, binding: Binding) -> Result<(), Conflict> {
+		match self.bindings.iter().find(|bound| bound.binding == binding) {
+			Some(bound) if bound.command != command => Err(Conflict {
+				binding,
+				commands: (bound.command, command),
+			}),
+			Some(_) => Ok(()),
+			None => {
+				self.bindings.push(Bound { command, binding });
+				Ok(())
+			}
+		}
+	}
+	pub fn unbind(&mut self, binding: Binding) {
+		self.bindings.retain(|bound| bound.binding != binding);
+	}
+	// Replaces every binding of `command` with `binding`
+	pub fn rebind(&mut self, command: GameCommand, binding: Binding) -> Result<(), Conflict> {
+		if let Some(bound) = self
+			.bindings
+			.iter()
+			.find(|bound| bound.binding == binding && bound.command != command)
+		{
+			return Err(Conflict {
+				binding,
+				commands: (bound.command, command),
+			});
+		}
+		self.bindings.retain(|bound| bound.command != command);
+		self.bind(command, binding)
+	} // Swaps one binding for another, the command's other bindings are kept
+	pub fn replace(&mut self, bound: Bound, binding: Binding) -> Result<(), Conflict> {
+		self.bind(bound.command, binding)?;
+		if binding != bound.binding {
+			self.unbind(bound.binding);
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		GameCommand::*,
+		VirtualKeyCode::*,
+	};
+
+	fn key(key: VirtualKeyCode) -> Binding {
+		Input::Key(key).into()
+	}
+
+	#[test]
+	fn defaults_have_no_conflicts() {
+		assert_eq!(InputMap::default().conflicts(), vec![]);
+	}
+
+	#[test]
+	fn conflicts() {
+		let mut input_map = InputMap::default();
+		input_map.bindings.push(Bound {
+			command: MoveUp,
+			binding: key(W), // Bound twice to the same command isn't a conflict
+		});
+		assert_eq!(input_map.conflicts(), vec![]);
+		input_map.bindings.push(Bound {
+			command: MoveLeft,
+			binding: key(D),
+		});
+		assert_eq!(
+			input_map.conflicts(),
+			vec![Conflict {
+				binding: key(D),
+				commands: (MoveRight, MoveLeft),
+			}]
+		);
+		assert_eq!(
+			InputMap::default()
+				.bind(MoveToCursor, key(W))
+				.unwrap_err()
+				.commands,
+			(MoveUp, MoveToCursor)
+		);
+		// Different modifiers are different bindings
+		assert_eq!(
+			InputMap::default().bind(
+				MoveToCursor,
+				Binding {
+					input: Input::Key(W),
+					modifiers: SHIFT,
+				}
+			),
+			Ok(())
+		);
+	}
+
+	#[test]
+	fn modifier_fallback() {
+		let mut input_map = InputMap::default();
+		let w = Input::Key(W);
+		input_map
+			.bind(
+				MoveToCursor,
+				Binding {
+					input: w,
+					modifiers: SHIFT,
+				},
+			)
+			.unwrap();
+		assert_eq!(input_map.command(w, Modifiers::default()), Some(MoveUp));
+		assert_eq!(input_map.command(w, SHIFT), Some(MoveToCursor));
+		// Nothing is bound to Ctrl+W, so it falls back to W
+		let ctrl = Modifiers {
+			ctrl: true,
+			..Modifiers::default()
+		};
+		assert_eq!(input_map.command(w, ctrl), Some(MoveUp));
+		assert_eq!(input_map.command(Input::Key(Q), SHIFT), None);
+	}
+
+	#[test]
+	fn replace_keeps_other_bindings() {
+		let mut input_map = InputMap::default();
+		let bound = input_map
+			.bound(Input::Key(W), Modifiers::default())
+			.unwrap();
+		input_map.replace(bound, key(K)).unwrap();
+		assert_eq!(
+			input_map.bindings_for(MoveUp).collect::<Vec<_>>(),
+			vec![&key(Up), &key(K)]
+		);
+		assert!(input_map.replace(bound, key(S)).is_err()); // Taken by MoveDown
+	}
+
+	#[test]
+	fn round_trip() {
+		let input_map = InputMap::default();
+		let source = toml::to_string(&input_map).unwrap();
+		assert_eq!(toml::from_str::<InputMap>(&source).unwrap(), input_map);
+	}
+
+	#[test]
+	fn needs_one_input() {
+		let parse = |source: &str| toml::from_str::<InputMap>(source);
+		assert!(parse("[[bindings]]\ncommand = \"MoveUp\"\nkey = \"W\"\n").is_ok());
+		assert!(
+			parse("[[bindings]]\ncommand = \"MoveUp\"\nmouse = \"Left\"\nshift = true\n").is_ok()
+		);
+		assert!(
+			parse("[[bindings]]\ncommand = \"MoveUp\"\nkey = \"W\"\nmouse = \"Left\"\n").is_err()
+		);
+		assert!(parse("[[bindings]]\ncommand = \"MoveUp\"\nshift = true\n").is_err());
+	}
+}
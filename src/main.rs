@@ -1,31 +1,56 @@
 use std::{
-	sync::{
-		Arc,
-		Mutex,
-	},
+	env,
+	path::PathBuf,
+	sync::mpsc,
 	thread,
 	time::Duration,
 };
 
+mod cli;
+use cli::Command;
+pub mod error;
+pub use error::Error;
+pub mod events;
+pub mod replay;
+use {
+	events::{
+		EventBus,
+		GameEvent,
+	},
+	replay::Recorder,
+};
 mod frame_manager;
 use frame_manager::FrameManager;
+pub mod scheduler;
+use scheduler::Scheduler;
 mod tick_manager;
 use tick_manager::TickManager;
 pub mod control_manager;
+pub mod gamepad;
+pub mod input_map;
 use control_manager::ControlManager;
 
 mod pixel_tile_conversion;
 pub use pixel_tile_conversion::*;
 
+pub mod animation;
 pub mod atlas;
 pub mod tile;
 pub mod world;
-use world::World;
+use world::{
+	clock::Clock,
+	save::Save,
+	World,
+};
 
 pub mod assets;
 
 pub use world::tile::TILE_SIZE;
 
+// World
+const GROUND_SEED: &str = "Wet";
+const RESOURCE_SEED: &str = "Shiny";
+
 // Board
 const CHUNK_X: i64 = 4; // Size of generation
 const CHUNK_Y: i64 = 4; // Size of generation
@@ -39,33 +64,141 @@ const MOVE_TIME: u64 = 3; // :. 0.5s
 						  //const CHOP_TIME: u64 = 20; // :. 2s
 
 fn main() {
-	let world = World::new("Wet", "Shiny");
-	let control_manager = ControlManager::new();
-	//let mut move_by: (i64, i64) = (0, 0);
+	error::install_panic_hook();
+	match Command::from_args(env::args().skip(1)) {
+		Ok(Command::Play {
+			dev,
+			speed,
+			clock,
+			record,
+			save,
+		}) => play(dev, speed, clock, record, save),
+		Ok(Command::Replay { path }) => {
+			if let Err(error) = cli::replay(path) {
+				eprintln!("{}", error);
+				std::process::exit(1);
+			}
+		}
+		Ok(Command::ExportMap {
+			ground_seed,
+			resource_seed,
+			depth,
+			pos,
+			size,
+			image,
+		}) => {
+			if let Err(error) = cli::export_map(ground_seed, resource_seed, depth, pos, size, image)
+			{
+				eprintln!("{}", error);
+				std::process::exit(1);
+			}
+		}
+		Ok(Command::PackAtlas { image, index }) => {
+			if let Err(error) = cli::pack_atlas(image, index) {
+				eprintln!("{}", error);
+				std::process::exit(1);
+			}
+		}
+		Err(usage) => {
+			eprintln!("{}", usage);
+			std::process::exit(1);
+		}
+	}
+}
 
-	let world_arc = Arc::new(Mutex::new(world));
-	let control_arc = Arc::new(Mutex::new(control_manager));
+fn play(dev: bool, speed: f64, clock: Clock, record: Option<PathBuf>, save_dir: Option<PathBuf>) {
+	// Read before opening the window, so a broken save doesn't flash one up
+	let save = match &save_dir {
+		Some(dir) if Save::exists(dir) => match Save::load(dir) {
+			Ok(save) => Some(save),
+			Err(error) => {
+				eprintln!("{}", error);
+				std::process::exit(1);
+			}
+		},
+		_ => None,
+	};
+	let (input_sender, input_receiver) = mpsc::channel();
+	let (snapshot_sender, snapshot_receiver) = mpsc::channel();
+	let mut scheduler = Scheduler::new(Duration::from_millis(TICK_LEN));
+	scheduler.set_scale(speed);
+	if let Some(save) = &save {
+		scheduler.set_ticks(save.ticks());
+	}
+	let mut events = EventBus::new();
+	events.on(|tick_events| {
+		for event in &tick_events.events {
+			if let GameEvent::Rebind(event) = event {
+				println!("{}", event); // The only way to follow along for now
+			}
+		}
+	});
+	if dev {
+		events.on(|tick_events| {
+			tick_events
+				.events
+				.iter()
+				.filter(|event| {
+					!matches!(
+						event,
+						GameEvent::ChunkGenerated { .. } // Too many to read
+							| GameEvent::Rebind(..) // Already printed
+					)
+				})
+				.for_each(|event| println!("Tick {}: {:?}", tick_events.tick, event))
+		});
+	}
 
-	let mut frame_manager = FrameManager::new(world_arc.clone(), control_arc.clone());
-	let mut tick_manager = TickManager::new(world_arc, control_arc);
+	let mut frame_manager = match FrameManager::new(input_sender, snapshot_receiver, dev) {
+		Ok(frame_manager) => frame_manager,
+		Err(error) => error::crash(error),
+	};
 
-	thread::spawn(move || loop {
-		tick_manager.run_once().unwrap();
-		thread::sleep(normalise_to(TICK_LEN, tick_manager.tick_gap as u64));
+	// The world can't leave the thread it's made on, so the tick thread owns it
+	let spawned = thread::Builder::new().name("tick".into()).spawn(move || {
+		let world = match save {
+			Some(save) => save.into_world(),
+			None => {
+				let mut world = World::new(GROUND_SEED, RESOURCE_SEED);
+				world.clock = clock;
+				world
+			}
+		};
+		let control_manager = ControlManager::new();
+		let recorder = record.map(|path| Recorder::new(path, &world, &control_manager.input_map));
+		let mut tick_manager = TickManager::new(
+			world,
+			control_manager,
+			scheduler,
+			input_receiver,
+			snapshot_sender,
+			events,
+		);
+		if let Some(recorder) = recorder {
+			tick_manager.record(recorder);
+		}
+		if let Some(dir) = save_dir {
+			tick_manager.save_to(dir);
+		}
+		tick_manager.save_bindings_to(input_map::InputMap::config_path());
+		tick_manager.run()
 	});
-	loop {
-		frame_manager.run_once() // For some reason render goes yuck if done from another thread
+	let tick_thread = match spawned {
+		Ok(tick_thread) => tick_thread,
+		Err(error) => error::crash(Error::SpawnTickThread(error.to_string())),
+	};
+	while !frame_manager.is_closed() {
+		// For some reason render goes yuck if done from another thread
+		if let Err(error) = frame_manager.run_once() {
+			error::crash(error);
+		}
+	}
+	drop(frame_manager); // Hangs up on the tick thread
+	if tick_thread.join().is_err() {
+		error::crash(Error::TickThreadStopped);
 	}
 }
 
 pub fn normalise_to(aim_ms: u64, recent_ms: u64) -> Duration {
 	Duration::from_millis(std::cmp::max(((aim_ms * 3) as i64 - recent_ms as i64) / 2, 0) as u64)
 }
-
-#[derive(Debug)]
-pub enum Error {
-	MissingTexture,
-	AnimationUnimplemented,
-	ControlManagerLocked,
-	WorldManagerLocked,
-}
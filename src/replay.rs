@@ -0,0 +1,310 @@
+use {
+	crate::{
+		control_manager::ControlManager,
+		events::EventBus,
+		input_map::InputMap,
+		scheduler::Scheduler,
+		tick_manager::{
+			TickInput,
+			TickManager,
+		},
+		world::clock::Clock,
+		World,
+		TICK_LEN,
+	},
+	serde::{
+		Deserialize,
+		Serialize,
+	},
+	std::{
+		fmt,
+		fs,
+		path::{
+			Path,
+			PathBuf,
+		},
+		sync::mpsc,
+		time::Duration,
+	},
+};
+
+const SAVE_EVERY: u64 = 100; // Ticks, so a crash loses at most 10s
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedInput {
+	pub tick: u64, // Applied before this tick ran
+	#[serde(flatten)]
+	pub input: TickInput,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+	Read { path: PathBuf, error: String },
+	Parse { path: PathBuf, error: String },
+	Write { path: PathBuf, error: String },
+	Mismatch { expected: String, found: String },
+}
+impl fmt::Display for ReplayError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use ReplayError::*;
+		match self {
+			Read { path, error } => write!(f, "could not read replay {:?}: {}", path, error),
+			Parse { path, error } => write!(f, "could not parse replay {:?}: {}", path, error),
+			Write { path, error } => write!(f, "could not write replay {:?}: {}", path, error),
+			Mismatch { expected, found } => write!(
+				f,
+				"replay diverged, expected world hash {} but found {}",
+				expected, found
+			),
+		}
+	}
+}
+
+// Everything needed to rerun a session, and the state it should end in
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+	pub ground_seed: String,
+	pub resource_seed: String,
+	pub final_tick: u64, // Ticks run when saved
+	pub world_hash: String,
+	#[serde(default)]
+	pub clock: Clock,
+	pub input_map: InputMap,
+	pub inputs: Vec<RecordedInput>,
+}
+impl Replay {
+	pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+		let path = path.as_ref();
+		let source = fs::read_to_string(path).map_err(|error| ReplayError::Read {
+			path: path.to_path_buf(),
+			error: error.to_string(),
+		})?;
+		toml::from_str(&source).map_err(|error| ReplayError::Parse {
+			path: path.to_path_buf(),
+			error: error.to_string(),
+		})
+	}
+	pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+		let path = path.as_ref();
+		let write_error = |error: String| ReplayError::Write {
+			path: path.to_path_buf(),
+			error,
+		};
+		let source = toml::to_string(self).map_err(|error| write_error(error.to_string()))?;
+		fs::write(path, source).map_err(|error| write_error(error.to_string()))
+	}
+	// Feeds the inputs back through a tick manager without a window
+	pub fn run(&self) -> World {
+		let (_input_sender, inputs) = mpsc::channel();
+		let (snapshots, _snapshot_receiver) = mpsc::channel();
+		let mut world = World::new(&self.ground_seed, &self.resource_seed);
+		world.clock = self.clock;
+		let mut tick_manager = TickManager::new(
+			world,
+			ControlManager::with_input_map(self.input_map.clone()),
+			Scheduler::new(Duration::from_millis(TICK_LEN)),
+			inputs,
+			snapshots,
+			EventBus::new(),
+		);
+		let mut recorded = self.inputs.iter().peekable();
+		for tick in 0..=self.final_tick {
+			while let Some(recorded) = recorded.next_if(|recorded| recorded.tick == tick) {
+				tick_manager.handle(recorded.input);
+			}
+			if tick < self.final_tick {
+				tick_manager.run_once(tick);
+			}
+		}
+		tick_manager.into_world()
+	}
+	pub fn verify(&self) -> Result<(), ReplayError> {
+		let found = format!("{:016x}", self.run().state_hash());
+		if found == self.world_hash {
+			Ok(())
+		} else {
+			Err(ReplayError::Mismatch {
+				expected: self.world_hash.clone(),
+				found,
+			})
+		}
+	}
+}
+
+// Collects a session as it's played, saving it now and then
+pub struct Recorder {
+	path: PathBuf,
+	replay: Replay,
+}
+impl Recorder {
+	pub fn new(path: PathBuf, world: &World, input_map: &InputMap) -> Self {
+		Self {
+			path,
+			replay: Replay {
+				ground_seed: world.ground_seed.clone(),
+				resource_seed: world.resource_seed.clone(),
+				final_tick: 0,
+				world_hash: format!("{:016x}", world.state_hash()),
+				clock: world.clock,
+				input_map: input_map.clone(),
+				inputs: vec![],
+			},
+		}
+	}
+	pub fn record(&mut self, tick: u64, input: TickInput) {
+		self.replay.inputs.push(RecordedInput { tick, input });
+	}
+	pub fn is_due(&self, ticks: u64) -> bool {
+		ticks >= self.replay.final_tick + SAVE_EVERY
+	}
+	// `ticks` is how many have run, which must be the state `world` is in
+	pub fn save(&mut self, world: &World, ticks: u64) -> Result<(), ReplayError> {
+		self.replay.final_tick = ticks;
+		self.replay.world_hash = format!("{:016x}", world.state_hash());
+		self.replay.save(&self.path)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		crate::{
+			control_manager::ControlInput,
+			events::GameEvent,
+			input_map::Modifiers,
+			tile::ResourceType,
+			world::tile::TilePos,
+			GROUND_SEED,
+			RESOURCE_SEED,
+		},
+		crow::glutin::event::VirtualKeyCode,
+		std::time::Instant,
+	};
+
+	fn press(keycode: VirtualKeyCode) -> TickInput {
+		TickInput::Control(ControlInput::Press {
+			keycode,
+			modifiers: Modifiers::default(),
+			cursor: (0, 0).into(),
+		})
+	}
+
+	fn release(keycode: VirtualKeyCode) -> TickInput {
+		TickInput::Control(ControlInput::Release { keycode })
+	}
+
+	// Plays `script` through the tick loop as the game would, each step's inputs arrive and
+	// then that many ticks run. Returns the saved replay, where the player ended up and what
+	// happened.
+	fn record(name: &str, script: &[(&[TickInput], u32)]) -> (Replay, TilePos, Vec<GameEvent>) {
+		let path =
+			std::env::temp_dir().join(format!("ky_space_{}_{}.toml", std::process::id(), name));
+		let (_input_sender, inputs) = mpsc::channel();
+		let (snapshots, _snapshot_receiver) = mpsc::channel();
+		let tick_len = Duration::from_millis(TICK_LEN);
+		let scheduler = Scheduler::new(tick_len);
+		let start = Instant::now();
+		let world = World::new(GROUND_SEED, RESOURCE_SEED);
+		let control_manager = ControlManager::with_input_map(InputMap::default());
+		let recorder = Recorder::new(path.clone(), &world, &control_manager.input_map);
+		let mut events = EventBus::new();
+		let published = events.subscribe();
+		let mut tick_manager =
+			TickManager::new(world, control_manager, scheduler, inputs, snapshots, events);
+		tick_manager.record(recorder);
+		let mut ticks = 0;
+		for (inputs, run) in script {
+			inputs.iter().for_each(|input| tick_manager.handle(*input));
+			for _ in 0..*run {
+				ticks += 1;
+				tick_manager.update(start + tick_len * ticks);
+			}
+		}
+		tick_manager.save_recording();
+		let world = tick_manager.into_world();
+		let replay = Replay::load(&path).unwrap();
+		fs::remove_file(&path).unwrap();
+		assert_eq!(replay.final_tick, u64::from(ticks));
+		let events = published
+			.try_iter()
+			.flat_map(|tick_events| tick_events.events.clone())
+			.collect();
+		(replay, *world.player.get_position(), events)
+	}
+
+	fn walk_about(name: &str) -> (Replay, TilePos) {
+		use VirtualKeyCode::*;
+		let (replay, player, _) = record(
+			name,
+			&[
+				(
+					&[TickInput::Resize {
+						size: (16, 12).into(),
+					}],
+					1,
+				),
+				(&[press(Right)], 12),
+				(&[release(Right), press(Up)], 8),
+				(&[release(Up), press(Down)], 4), // Back over tiles already walked
+			],
+		);
+		(replay, player)
+	}
+
+	#[test]
+	fn recorded_session_verifies() {
+		let (replay, player) = walk_about("verifies");
+		assert_ne!(player, (0, 0).into());
+		assert_eq!(replay.inputs.len(), 6);
+		assert_eq!(*replay.run().player.get_position(), player);
+		replay.verify().unwrap();
+	}
+
+	#[test]
+	fn tampering_is_a_mismatch() {
+		let (replay, _) = walk_about("tampering");
+		let mut tampered = replay.clone();
+		tampered.world_hash = format!("{:016x}", 1);
+		assert!(matches!(
+			tampered.verify(),
+			Err(ReplayError::Mismatch { .. })
+		));
+		let mut tampered = replay.clone();
+		tampered.inputs[1].input = press(VirtualKeyCode::Left);
+		assert!(matches!(
+			tampered.verify(),
+			Err(ReplayError::Mismatch { .. })
+		));
+		let mut tampered = replay;
+		tampered.inputs.pop(); // Pressing down
+		assert!(matches!(
+			tampered.verify(),
+			Err(ReplayError::Mismatch { .. })
+		));
+	}
+
+	#[test]
+	fn growth_replays() {
+		let (replay, _, events) = record(
+			"growth",
+			&[(
+				&[TickInput::Resize {
+					size: (96, 64).into(), // Loads well past where things grow
+				}],
+				1500,
+			)],
+		);
+		assert!(
+			events.iter().any(|event| matches!(
+				event,
+				GameEvent::TileChanged {
+					resource: ResourceType::Flower,
+					..
+				}
+			)),
+			"no flowers spread"
+		);
+		replay.verify().unwrap();
+	}
+}
@@ -0,0 +1,130 @@
+use std::{
+	ops::{
+		Range,
+		RangeInclusive,
+	},
+	time::{
+		Duration,
+		Instant,
+	},
+};
+
+const MAX_CATCH_UP: u32 = 5; // Ticks run back to back after a stall, the rest are dropped
+pub const SCALES: RangeInclusive<f64> = 0.05..=16.0; // Beyond these durations overflow
+
+// Fixed timestep, game time only moves forward in whole ticks
+#[derive(Debug)]
+pub struct Scheduler {
+	tick_len: Duration,
+	scale: f64,
+	paused: bool,
+	accumulated: Duration,
+	last_update: Instant,
+	ticks: u64,
+}
+impl Scheduler {
+	pub fn new(tick_len: Duration) -> Self {
+		Self {
+			tick_len,
+			scale: 1.0,
+			paused: false,
+			accumulated: Duration::ZERO,
+			last_update: Instant::now(),
+			ticks: 0,
+		}
+	}
+	// Ticks run so far, for timestamping game state
+	pub fn ticks(&self) -> u64 {
+		self.ticks
+	}
+	// Carries on from a save rather than the start
+	pub fn set_ticks(&mut self, ticks: u64) {
+		self.ticks = ticks;
+	}
+	// 2.0 runs the game twice as fast, 0.5 half as fast. Kept within `SCALES`.
+	pub fn set_scale(&mut self, scale: f64) {
+		if !scale.is_nan() {
+			self.scale = scale.clamp(*SCALES.start(), *SCALES.end());
+		}
+	}
+	pub fn is_paused(&self) -> bool {
+		self.paused
+	}
+	pub fn pause(&mut self) {
+		self.paused = true;
+	}
+	// Time spent paused is never caught up
+	pub fn resume(&mut self) {
+		if self.paused {
+			self.paused = false;
+			self.last_update = Instant::now();
+		}
+	}
+	pub fn toggle_pause(&mut self) {
+		if self.paused {
+			self.resume();
+		} else {
+			self.pause();
+		}
+	}
+	// The ticks due by `now`, each should be run once in order
+	pub fn advance(&mut self, now: Instant) -> Range<u64> {
+		let elapsed = now.saturating_duration_since(self.last_update);
+		self.last_update = now;
+		if self.paused {
+			return self.ticks..self.ticks;
+		}
+		self.accumulated += elapsed.mul_f64(self.scale);
+
+		let mut due = 0;
+		while self.accumulated >= self.tick_len {
+			self.accumulated -= self.tick_len;
+			due += 1;
+		}
+		if due > MAX_CATCH_UP {
+			eprintln!(
+				"Running {} ticks behind, skipping {}",
+				due,
+				due - MAX_CATCH_UP
+			);
+			due = MAX_CATCH_UP;
+		}
+		let start = self.ticks;
+		self.ticks += due as u64;
+		start..self.ticks
+	}
+	// How long until the next tick is due
+	pub fn until_next(&self) -> Duration {
+		if self.paused {
+			return self.tick_len;
+		}
+		(self.tick_len - self.accumulated).div_f64(self.scale)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn scale_kept_in_range() {
+		let tick_len = Duration::from_millis(100);
+		let mut scheduler = Scheduler::new(tick_len);
+		let start = scheduler.last_update;
+		for (scale, kept) in [
+			(f64::INFINITY, 16.0),
+			(1e300, 16.0),
+			(1e-300, 0.05),
+			(0.0, 0.05),
+			(-2.0, 0.05),
+			(f64::NAN, 0.05), // Ignored
+			(2.0, 2.0),
+		] {
+			scheduler.set_scale(scale);
+			assert_eq!(scheduler.scale, kept);
+			scheduler.until_next();
+		}
+		assert_eq!(scheduler.advance(start + tick_len * 2), 0..4);
+		assert_eq!(scheduler.until_next(), tick_len / 2);
+	}
+}
@@ -1,71 +1,380 @@
 use {
 	crate::{
-		control_manager::Action,
-		world::tile::Direction,
+		control_manager::{
+			Action,
+			ActionFailure,
+			ControlInput,
+			PlayerAction,
+			RebindEvent,
+		},
+		events::{
+			EventBus,
+			GameEvent,
+		},
+		input_map::GameCommand,
+		replay::Recorder,
+		scheduler::Scheduler,
+		world::{
+			save::{
+				Save,
+				AUTOSAVE_EVERY,
+			},
+			tile::{
+				Direction,
+				TilePos,
+			},
+			WorldSnapshot,
+		},
 		ControlManager,
-		Error,
 		World,
-		TICK_LEN,
+	},
+	serde::{
+		Deserialize,
+		Serialize,
 	},
 	std::{
-		sync::{
-			Arc,
-			Mutex,
+		path::PathBuf,
+		sync::mpsc::{
+			Receiver,
+			RecvTimeoutError,
+			Sender,
 		},
 		time::Instant,
 	},
 };
 
+// Sent from the render thread
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "input")]
+pub enum TickInput {
+	Control(ControlInput),
+	Resize { size: TilePos },          // Board size in tiles
+	Hover { cursor: Option<TilePos> }, // None once the cursor leaves the window
+}
+
+// Owns the game state on the tick thread, the render thread only sees snapshots
 pub struct TickManager {
-	world: Arc<Mutex<World>>,
-	control_manager: Arc<Mutex<ControlManager>>,
-	last_tick: Instant,
-	pub tick_gap: u128,
+	world: World,
+	control_manager: ControlManager,
+	scheduler: Scheduler,
+	inputs: Receiver<TickInput>,
+	snapshots: Sender<WorldSnapshot>,
+	events: EventBus,
+	paused_events: Vec<GameEvent>, // Pausing isn't part of the world or controls
+	map_open: bool,                // Nor is what's drawn
+	hovered: Option<TilePos>,
+	recorder: Option<Recorder>,
+	bindings_path: Option<PathBuf>, // Where rebinding saves to, not set for replays
+	save_dir: Option<PathBuf>,
+	saved_at: u64, // Ticks run when last saved
 }
 impl TickManager {
-	pub fn new(world: Arc<Mutex<World>>, control_manager: Arc<Mutex<ControlManager>>) -> Self {
+	pub fn new(
+		world: World,
+		control_manager: ControlManager,
+		scheduler: Scheduler,
+		inputs: Receiver<TickInput>,
+		snapshots: Sender<WorldSnapshot>,
+		events: EventBus,
+	) -> Self {
 		Self {
 			world,
 			control_manager,
-			last_tick: Instant::now(),
-			tick_gap: TICK_LEN as u128,
+			scheduler,
+			inputs,
+			snapshots,
+			events,
+			paused_events: vec![],
+			map_open: false,
+			hovered: None,
+			recorder: None,
+			bindings_path: None,
+			save_dir: None,
+			saved_at: 0,
+		}
+	}
+	pub fn record(&mut self, recorder: Recorder) {
+		self.recorder = Some(recorder);
+	}
+	pub fn save_bindings_to(&mut self, path: PathBuf) {
+		self.bindings_path = Some(path);
+	}
+	pub fn save_to(&mut self, dir: PathBuf) {
+		self.save_dir = Some(dir);
+		self.saved_at = self.scheduler.ticks();
+	}
+	pub fn into_world(self) -> World {
+		self.world
+	}
+	// Runs until the render thread hangs up
+	pub fn run(mut self) {
+		loop {
+			match self.inputs.recv_timeout(self.scheduler.until_next()) {
+				Ok(input) => self.handle(input),
+				Err(RecvTimeoutError::Timeout) => (),
+				Err(RecvTimeoutError::Disconnected) => break,
+			}
+			while let Ok(input) = self.inputs.try_recv() {
+				self.handle(input);
+			}
+			if !self.update(Instant::now()) {
+				break;
+			}
+		}
+		self.save_recording();
+		self.save();
+	}
+	// Runs the ticks due by `now` and sends a snapshot, false once the render thread hangs up
+	pub fn update(&mut self, now: Instant) -> bool {
+		for tick in self.scheduler.advance(now) {
+			self.run_once(tick);
+		}
+		for error in self.world.load_view() {
+			eprintln!("{}", error); // The chunk is left empty
+		}
+		self.publish(self.world.tick); // From input and loading, between ticks
+		if self
+			.recorder
+			.as_ref()
+			.is_some_and(|recorder| recorder.is_due(self.scheduler.ticks()))
+		{
+			self.save_recording();
+		}
+		if self.scheduler.ticks() >= self.saved_at + AUTOSAVE_EVERY {
+			self.save();
+		}
+		self.snapshots
+			.send(self.world.snapshot(self.map_open, self.hovered))
+			.is_ok()
+	}
+	pub fn save_recording(&mut self) {
+		if let Some(recorder) = self.recorder.as_mut() {
+			if let Err(error) = recorder.save(&self.world, self.scheduler.ticks()) {
+				eprintln!("{}", error);
+			}
+		}
+	}
+	pub fn save(&mut self) {
+		if let Some(dir) = &self.save_dir {
+			self.saved_at = self.scheduler.ticks();
+			if let Err(error) = Save::of(&self.world, self.saved_at).write(dir) {
+				eprintln!("{}", error); // Tried again at the next autosave
+			}
+		}
+	}
+	pub fn handle(&mut self, input: TickInput) {
+		if let Some(recorder) = self.recorder.as_mut() {
+			// Hovering only changes what's drawn, and there'd be a lot of it
+			if !matches!(input, TickInput::Hover { .. }) {
+				recorder.record(self.scheduler.ticks(), input);
+			}
+		}
+		match input {
+			TickInput::Control(input) => match self.control_manager.apply(input) {
+				Some(GameCommand::TogglePause) => {
+					self.scheduler.toggle_pause();
+					self.paused_events.push(if self.scheduler.is_paused() {
+						GameEvent::Paused
+					} else {
+						GameEvent::Resumed
+					});
+				}
+				Some(GameCommand::ToggleMap) => self.map_open = !self.map_open,
+				Some(GameCommand::ToggleWaypoint) => {
+					if let Some(cursor) = input.cursor() {
+						self.world.toggle_waypoint(cursor);
+					}
+				}
+				_ => (),
+			},
+			TickInput::Resize { size } => self.world.view_size = size,
+			TickInput::Hover { cursor } => self.hovered = cursor,
 		}
 	}
-	pub fn run_once(&mut self) -> Result<(), Error> {
-		self.tick_gap = self.last_tick.elapsed().as_millis();
-		self.last_tick = Instant::now();
-		let mut control_manager = self.control_manager.lock().unwrap(); // TODO: Handle
+	pub fn run_once(&mut self, tick: u64) {
+		self.world.set_tick(tick);
+		for error in self.world.grow() {
+			eprintln!("{}", error);
+		}
+		self.step();
+		for error in self.world.look() {
+			eprintln!("{}", error); // Nothing to see there
+		}
+		self.publish(tick);
+	}
+	fn publish(&mut self, tick: u64) {
+		let rebind_events = self.control_manager.drain_rebind_events();
+		if rebind_events
+			.iter()
+			.any(|event| matches!(event, RebindEvent::Rebound { .. }))
+		{
+			self.save_bindings();
+		}
+		let events = self
+			.paused_events
+			.drain(..)
+			.chain(
+				self.control_manager
+					.drain_events()
+					.into_iter()
+					.map(GameEvent::Action),
+			)
+			.chain(rebind_events.into_iter().map(GameEvent::Rebind))
+			.chain(self.world.drain_events())
+			.collect();
+		self.events.publish(tick, events);
+	}
+	fn save_bindings(&self) {
+		if let Some(path) = &self.bindings_path {
+			if let Err(error) = self.control_manager.input_map.save(path) {
+				eprintln!("{}", error); // Kept for this session only
+			}
+		}
+	}
+	fn step(&mut self) {
+		let control_manager = &mut self.control_manager;
 		use Action::*;
-		let pending_action = control_manager.pending_action.take();
-		match pending_action {
-			Some(mut player_action) => {
-				if player_action.tick() {
-					match player_action.action {
-						MoveTo(pos) => {
-							if let Ok(mut world) = self.world.lock() {
-								let player_pos = *world.player.get_position();
-								let distance = player_pos - &pos;
-								if distance == (0, 0).into() {
-									(*control_manager).complete_pending();
-									world.player.stopped_moving();
-									return Ok(());
-								} else {
-									let direction = Direction::from(distance);
-									world.player.move_by(direction.into());
-								}
-							} else {
-								return Err(Error::WorldManagerLocked);
-							}
-						}
+		control_manager.start_current();
+		// Releasing every movement key stops walking straight away
+		if let Some(PlayerAction {
+			action: Walk { first_step: None },
+			..
+		}) = control_manager.current()
+		{
+			if control_manager.held_direction() == (0, 0).into() {
+				control_manager.complete_current();
+				self.world.stop_player();
+				return;
+			}
+		}
+		let action = match control_manager.current_mut() {
+			Some(player_action) => {
+				if !player_action.tick() {
+					return;
+				}
+				player_action.action.clone()
+			}
+			None => return,
+		};
+		match action {
+			Walk { first_step } => {
+				let held = control_manager.held_direction();
+				let direction = match first_step {
+					Some(first_step) if held == (0, 0).into() => first_step,
+					_ => held,
+				};
+				if let Some(player_action) = control_manager.current_mut() {
+					player_action.action = Walk { first_step: None };
+				}
+				if self.world.can_step(direction) {
+					self.world.move_player(direction);
+				}
+			}
+			MoveTo(pos) => {
+				let player_pos = *self.world.player.get_position();
+				let distance = player_pos - &pos;
+				if distance == (0, 0).into() {
+					control_manager.complete_current();
+					self.world.stop_player();
+				} else {
+					let direction = TilePos::from(Direction::from(distance));
+					if self.world.can_step(direction) {
+						self.world.move_player(direction);
+					} else {
+						control_manager.fail_current(ActionFailure::Blocked);
+						self.world.stop_player();
+					}
+				}
+			}
+			Harvest(pos) => {
+				let player_pos = *self.world.player.get_position();
+				let distance = player_pos - &pos;
+				if distance.x.abs() > 1 || distance.y.abs() > 1 {
+					let direction = TilePos::from(Direction::from(distance));
+					if self.world.can_step(direction) {
+						self.world.move_player(direction);
+					} else {
+						control_manager.fail_current(ActionFailure::Blocked);
+						self.world.stop_player();
+					}
+					return;
+				}
+				self.world.stop_player();
+				match self.world.harvest(pos) {
+					Ok(Some(_)) => {
+						control_manager.complete_current();
+					}
+					Ok(None) => {
+						control_manager.fail_current(ActionFailure::NothingToHarvest);
+					}
+					Err(error) => {
+						eprintln!("{}", error); // Couldn't generate the chunk, so nothing's there
+						control_manager.fail_current(ActionFailure::NothingToHarvest);
 					}
 				}
-				control_manager.pending_action = Some(player_action);
 			}
-			None => {}
 		}
-		Ok(())
 	}
 }
-// TODO: Confirm safety
-unsafe impl Sync for TickManager {}
-unsafe impl Send for TickManager {}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		crate::{
+			input_map::{
+				InputMap,
+				Modifiers,
+			},
+			tile::ResourceType,
+			TICK_LEN,
+		},
+		crow::glutin::event::VirtualKeyCode,
+		std::{
+			sync::mpsc,
+			time::Duration,
+		},
+	};
+
+	#[test]
+	fn diagonals_blocked_by_corners() {
+		let mut world = World::new("Wet", "Shiny");
+		let block = |world: &mut World, pos: (i64, i64), resource: ResourceType| {
+			let pos = pos.into();
+			world.can_walk(&pos); // Generates it
+			let ground = world.ground_at(&pos).unwrap().clone();
+			world.set_tile(pos, ground, resource).unwrap();
+		};
+		block(&mut world, (0, 0), ResourceType::None);
+		block(&mut world, (1, 1), ResourceType::None);
+		block(&mut world, (1, 0), ResourceType::Rock);
+		block(&mut world, (0, 1), ResourceType::Tree);
+
+		let (_input_sender, inputs) = mpsc::channel();
+		let (snapshots, _snapshot_receiver) = mpsc::channel();
+		let tick_len = Duration::from_millis(TICK_LEN);
+		let mut tick_manager = TickManager::new(
+			world,
+			ControlManager::with_input_map(InputMap::default()),
+			Scheduler::new(tick_len),
+			inputs,
+			snapshots,
+			EventBus::new(),
+		);
+		for keycode in [VirtualKeyCode::Right, VirtualKeyCode::Up] {
+			tick_manager.handle(TickInput::Control(ControlInput::Press {
+				keycode,
+				modifiers: Modifiers::default(),
+				cursor: (0, 0).into(),
+			}));
+		}
+		let start = Instant::now();
+		for tick in 1..10 {
+			tick_manager.update(start + tick_len * tick);
+		}
+		assert_eq!(
+			*tick_manager.into_world().player.get_position(),
+			(0, 0).into()
+		);
+	}
+}
@@ -1,22 +1,15 @@
 use {
 	crate::{
-		atlas::{
-			Atlas,
-			SpriteTexture,
-			TextureType,
-		},
+		atlas::TextureType,
 		frame_manager::draw::Draw,
-		world::tile::PixelPos,
-		Error,
 	},
-	crow::{
-		Context,
-		DrawConfig,
-		WindowSurface,
+	serde::{
+		Deserialize,
+		Serialize,
 	},
 };
 
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GroundType {
 	Water,
 	PlainGrass,
@@ -24,103 +17,111 @@ pub enum GroundType {
 	WindSweptGrass,
 	Dirt,
 	Stone,
+	Sand,   // Shores, only made by hydrology
+	Bricks, // Ruin floors, only made by structures
 }
-#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ResourceType {
 	Rock,
 	Bush,
 	Tree,
 	Flower,
 	None,
+	Stump,
+	Sapling,
+	YoungTree,
+	BareBush, // Picked bare of berries
+	Conifer,
+	JunglePlant,
+	Cactus,
+	Mushroom,
+	Reeds,
+	IronOre,
+	CopperOre,
+	SilverOre,
+	Firewood,
+	Log,
+	CaveEntrance,
+	Ladder, // Up out of a cave
 }
-impl Draw for GroundType {
-	fn draw_animated(
-		&self,
-		ctx: &mut Context,
-		surface: &mut WindowSurface,
-		pos: PixelPos,
-		atlas: &Atlas,
-		frame: usize,
-	) -> Result<(), Error> {
-		let texture = atlas
-			.atlas
-			.get(&TextureType::Ground(self.clone()))
-			.ok_or(Error::MissingTexture)?;
-		if let SpriteTexture::Animated(textures) = texture {
-			Ok(ctx.draw(
-				surface,
-				&textures[frame % textures.len()],
-				pos.into(),
-				&DrawConfig {
-					scale: (4, 4),
-					..DrawConfig::default()
-				},
-			))
-		} else {
-			Err(Error::MissingTexture)
+impl GroundType {
+	// Higher layers bleed over the edges of lower ones
+	pub fn layer(&self) -> u8 {
+		use GroundType::*;
+		match self {
+			Stone => 0,
+			Sand => 1,
+			Dirt => 2,
+			PlainGrass => 3,
+			Grass => 4,
+			WindSweptGrass => 5,
+			Bricks => 6,
+			Water => 7,
+		}
+	}
+}
+impl ResourceType {
+	// What's left behind, None if it can't be harvested
+	pub fn harvested(&self) -> Option<ResourceType> {
+		use ResourceType::*;
+		match self {
+			Tree | YoungTree | Conifer => Some(Stump), // Stumps grow back as broadleaf trees
+			Bush => Some(BareBush),
+			Flower | Sapling | JunglePlant | Cactus | Mushroom | Reeds | Firewood | Log => {
+				Some(None)
+			}
+			IronOre | CopperOre | SilverOre => Some(Rock),
+			Rock | None | Stump | BareBush | CaveEntrance | Ladder => Option::None,
 		}
 	}
-	fn draw_still(
-		&self,
-		ctx: &mut Context,
-		surface: &mut WindowSurface,
-		pos: PixelPos,
-		atlas: &Atlas,
-	) -> Result<(), Error> {
-		let texture = atlas
-			.atlas
-			.get(&TextureType::Ground(self.clone()))
-			.ok_or(Error::MissingTexture)?;
-		match texture {
-			SpriteTexture::Still(texture) => ctx.draw(
-				surface,
-				texture,
-				pos.into(),
-				&DrawConfig {
-					scale: (4, 4),
-					..DrawConfig::default()
-				},
-			),
-			SpriteTexture::Animated(textures) => ctx.draw(
-				surface,
-				&textures[fastrand::usize(..textures.len())],
-				pos.into(),
-				&DrawConfig {
-					scale: (4, 4),
-					..DrawConfig::default()
-				},
-			),
+	pub fn is_walkable(&self) -> bool {
+		use ResourceType::*;
+		match self {
+			None | Flower | Stump | Sapling | Mushroom | Reeds | CaveEntrance | Ladder => true,
+			Rock | Bush | Tree | YoungTree | BareBush | Conifer | JunglePlant | Cactus
+			| IronOre | CopperOre | SilverOre | Firewood | Log => false,
 		}
-		Ok(())
+	}
+	// Trees and rocks, the player can't see past them
+	pub fn blocks_sight(&self) -> bool {
+		use ResourceType::*;
+		match self {
+			Rock | Tree | YoungTree | Conifer | IronOre | CopperOre | SilverOre => true,
+			None | Bush | Flower | Stump | Sapling | BareBush | JunglePlant | Cactus | Mushroom
+			| Reeds | Firewood | Log | CaveEntrance | Ladder => false,
+		}
+	}
+	// The next growth stage and how many ticks of normal growth it takes
+	pub fn grows_into(&self) -> Option<(ResourceType, u64)> {
+		use ResourceType::*;
+		match self {
+			Stump => Some((Sapling, 600)),
+			Sapling => Some((YoungTree, 1200)),
+			YoungTree => Some((Tree, 1800)),
+			BareBush => Some((Bush, 900)),
+			Rock | Bush | Tree | Flower | None | Conifer | JunglePlant | Cactus | Mushroom
+			| Reeds | IronOre | CopperOre | SilverOre | Firewood | Log | CaveEntrance | Ladder => {
+				Option::None
+			}
+		}
+	}
+}
+impl Draw for GroundType {
+	fn texture_type(&self) -> Option<TextureType> {
+		Some(TextureType::Ground(self.clone()))
 	}
 }
 impl Draw for ResourceType {
-	fn draw_still(
-		&self,
-		ctx: &mut Context,
-		surface: &mut WindowSurface,
-		pos: PixelPos,
-		atlas: &Atlas,
-	) -> Result<(), Error> {
-		if matches!(self, ResourceType::None) {
-			Ok(())
-		} else {
-			atlas
-				.atlas
-				.get(&TextureType::Resource(self.clone()))
-				.map(|texture| match texture {
-					SpriteTexture::Still(texture) => ctx.draw(
-						surface,
-						texture,
-						pos.into(),
-						&DrawConfig {
-							scale: (4, 4),
-							..DrawConfig::default()
-						},
-					),
-					_ => unimplemented!(),
-				})
-				.ok_or(Error::MissingTexture)
+	fn texture_type(&self) -> Option<TextureType> {
+		match self {
+			ResourceType::None => None,
+			resource => Some(TextureType::Resource(resource.clone())),
+		}
+	}
+	fn scale(&self) -> u32 {
+		match self {
+			ResourceType::YoungTree => 2, // From a sheet of double sized cells
+			_ => 4,
 		}
 	}
 }
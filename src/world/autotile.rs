@@ -0,0 +1,226 @@
+use {
+	super::tile::TilePos,
+	crate::{
+		atlas::TextureType,
+		frame_manager::draw::Draw,
+		tile::GroundType,
+	},
+	serde::Deserialize,
+};
+
+// Named by the side of the tile the neighbouring ground bleeds in from
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Deserialize)]
+pub enum Piece {
+	EdgeN,
+	EdgeE,
+	EdgeS,
+	EdgeW,
+	OuterNW,
+	OuterNE,
+	OuterSE,
+	OuterSW,
+	InnerNW,
+	InnerNE,
+	InnerSE,
+	InnerSW,
+}
+impl Piece {
+	pub const ALL: [Piece; 12] = [
+		Piece::EdgeN,
+		Piece::EdgeE,
+		Piece::EdgeS,
+		Piece::EdgeW,
+		Piece::OuterNW,
+		Piece::OuterNE,
+		Piece::OuterSE,
+		Piece::OuterSW,
+		Piece::InnerNW,
+		Piece::InnerNE,
+		Piece::InnerSE,
+		Piece::InnerSW,
+	];
+	// Cell within a standard LPC terrain block (3 wide, 7 tall)
+	pub fn block_cell(&self) -> (u32, u32) {
+		use Piece::*;
+		match self {
+			InnerNW => (1, 0),
+			InnerNE => (2, 0),
+			InnerSW => (1, 1),
+			InnerSE => (2, 1),
+			OuterSE => (0, 2),
+			EdgeS => (1, 2),
+			OuterSW => (2, 2),
+			EdgeE => (0, 3),
+			EdgeW => (2, 3),
+			OuterNE => (0, 4),
+			EdgeN => (1, 4),
+			OuterNW => (2, 4),
+		}
+	}
+}
+
+// Neighbour offsets in mask bit order, +y is north
+pub const NEIGHBOURS: [(i64, i64); 8] = [
+	(-1, 1),  // NW
+	(0, 1),   // N
+	(1, 1),   // NE
+	(1, 0),   // E
+	(1, -1),  // SE
+	(0, -1),  // S
+	(-1, -1), // SW
+	(-1, 0),  // W
+];
+const NW: u8 = 1 << 0;
+const N: u8 = 1 << 1;
+const NE: u8 = 1 << 2;
+const E: u8 = 1 << 3;
+const SE: u8 = 1 << 4;
+const S: u8 = 1 << 5;
+const SW: u8 = 1 << 6;
+const W: u8 = 1 << 7;
+
+pub fn neighbour_positions(pos: &TilePos) -> [TilePos; 8] {
+	NEIGHBOURS.map(|offset| *pos + &offset.into())
+}
+
+pub fn pieces(mask: u8) -> Vec<Piece> {
+	let has = |bit: u8| mask & bit != 0;
+	let mut covered = 0;
+	let mut pieces = vec![];
+	for (first, second, piece) in [
+		(N, W, Piece::InnerNW),
+		(N, E, Piece::InnerNE),
+		(S, E, Piece::InnerSE),
+		(S, W, Piece::InnerSW),
+	] {
+		if has(first) && has(second) {
+			pieces.push(piece);
+			covered |= first | second;
+		}
+	}
+	for (side, piece) in [
+		(N, Piece::EdgeN),
+		(E, Piece::EdgeE),
+		(S, Piece::EdgeS),
+		(W, Piece::EdgeW),
+	] {
+		if has(side) && covered & side == 0 {
+			pieces.push(piece);
+		}
+	}
+	for (corner, first, second, piece) in [
+		(NW, N, W, Piece::OuterNW),
+		(NE, N, E, Piece::OuterNE),
+		(SE, S, E, Piece::OuterSE),
+		(SW, S, W, Piece::OuterSW),
+	] {
+		if has(corner) && !has(first) && !has(second) {
+			pieces.push(piece);
+		}
+	}
+	pieces
+}
+
+// Higher layered ground bleeds over lower, drawn lowest first. Unloaded neighbours are ignored.
+pub fn transitions(center: &GroundType, neighbours: &[Option<GroundType>; 8]) -> Vec<Transition> {
+	let mut overlays: Vec<&GroundType> = neighbours
+		.iter()
+		.flatten()
+		.filter(|ground| ground.layer() > center.layer())
+		.collect();
+	overlays.sort_by_key(|ground| ground.layer());
+	overlays.dedup();
+	overlays
+		.into_iter()
+		.flat_map(|ground| {
+			let mask = neighbours
+				.iter()
+				.enumerate()
+				.filter(|(_, neighbour)| neighbour.as_ref() == Some(ground))
+				.fold(0, |mask, (bit, _)| mask | (1 << bit));
+			pieces(mask).into_iter().map(|piece| Transition {
+				ground: ground.clone(),
+				piece,
+			})
+		})
+		.collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transition {
+	pub ground: GroundType,
+	pub piece: Piece,
+}
+impl Draw for Transition {
+	fn texture_type(&self) -> Option<TextureType> {
+		Some(TextureType::Transition(self.ground.clone(), self.piece))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		GroundType::*,
+	};
+
+	#[test]
+	fn edges() {
+		assert_eq!(pieces(N), vec![Piece::EdgeN]);
+		assert_eq!(pieces(E | W), vec![Piece::EdgeE, Piece::EdgeW]);
+		// Corners beside an edge are part of it
+		assert_eq!(pieces(NW | N | NE), vec![Piece::EdgeN]);
+	}
+
+	#[test]
+	fn inner_corners() {
+		assert_eq!(pieces(N | W), vec![Piece::InnerNW]);
+		assert_eq!(pieces(S | E | SE), vec![Piece::InnerSE]);
+		assert_eq!(pieces(N | E | S), vec![Piece::InnerNE, Piece::InnerSE]);
+	}
+
+	#[test]
+	fn outer_corners() {
+		assert_eq!(pieces(NE), vec![Piece::OuterNE]);
+		assert_eq!(pieces(NW | SE), vec![Piece::OuterNW, Piece::OuterSE]);
+		assert_eq!(pieces(SW | E), vec![Piece::EdgeE, Piece::OuterSW]);
+		assert_eq!(pieces(0), vec![]);
+	}
+
+	#[test]
+	fn higher_layers_drawn_last() {
+		let mut neighbours: [Option<GroundType>; 8] = Default::default();
+		neighbours[1] = Some(Water); // N
+		neighbours[5] = Some(Grass); // S
+		neighbours[3] = Some(Stone); // E, lower so it doesn't bleed over
+		assert_eq!(
+			transitions(&Dirt, &neighbours),
+			vec![
+				Transition {
+					ground: Grass,
+					piece: Piece::EdgeS,
+				},
+				Transition {
+					ground: Water,
+					piece: Piece::EdgeN,
+				},
+			]
+		);
+	}
+
+	#[test]
+	fn same_or_lower_ground_ignored() {
+		let mut neighbours: [Option<GroundType>; 8] = Default::default();
+		assert_eq!(transitions(&Stone, &neighbours), vec![]); // Nothing loaded
+		neighbours[7] = Some(Grass); // W
+		assert_eq!(transitions(&Grass, &neighbours), vec![]);
+		assert_eq!(transitions(&Water, &neighbours), vec![]);
+		assert_eq!(
+			transitions(&Stone, &neighbours),
+			vec![Transition {
+				ground: Grass,
+				piece: Piece::EdgeW,
+			}]
+		);
+	}
+}
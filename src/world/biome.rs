@@ -0,0 +1,69 @@
+use crate::tile::{
+	GroundType,
+	ResourceType,
+};
+
+pub type Band = (f64, f64); // Of a noise map, exclusive
+
+pub const SEA_LEVEL: f64 = -0.5; // Of the ground map, anything lower is water
+
+// Ground by height, the first that fits is used and anything outside them is dirt
+pub const GROUND_HEIGHTS: [(GroundType, Band); 6] = [
+	(GroundType::Water, (-1.5, SEA_LEVEL)),
+	(GroundType::PlainGrass, (SEA_LEVEL, -0.4)),
+	(GroundType::Grass, (-0.4, 0.1)),
+	(GroundType::WindSweptGrass, (0.1, 0.4)),
+	(GroundType::Dirt, (0.4, 1.0)),
+	(GroundType::Stone, (1.0, 1.5)),
+];
+
+// How abundant a resource is, as a band of the resource noise map. Tiles outside every band
+// are left empty.
+const RARE: Band = (-0.1, -0.05);
+const SMALL: Band = (0.0, 0.1);
+const MEDIUM: Band = (0.1, 0.2);
+const LARGE: Band = (0.2, 0.35);
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Climate {
+	Cold,
+	Temperate,
+	Warm,
+}
+impl Climate {
+	pub const ALL: [Climate; 3] = [Climate::Cold, Climate::Temperate, Climate::Warm];
+	// Of the climate noise map
+	pub fn band(&self) -> Band {
+		match self {
+			Climate::Cold => (-1.5, -0.25),
+			Climate::Temperate => (-0.25, 0.25),
+			Climate::Warm => (0.25, 1.5),
+		}
+	}
+}
+
+// A biome is a ground type in a climate, this is what grows there. Earlier entries win where
+// bands overlap.
+pub fn spawn_table(ground: &GroundType, climate: Climate) -> Vec<(ResourceType, Band)> {
+	use {
+		Climate::*,
+		GroundType::*,
+		ResourceType::*,
+	};
+	match (ground, climate) {
+		(Water | Sand | Bricks, _) => vec![],
+		(PlainGrass, Warm) => vec![(Flower, SMALL), (Reeds, MEDIUM)],
+		(PlainGrass, _) => vec![(Flower, SMALL), (Reeds, RARE)],
+		(Grass, Cold) => vec![(Conifer, LARGE), (Bush, MEDIUM), (Mushroom, RARE)],
+		(Grass, Temperate) => vec![(Bush, MEDIUM), (Tree, LARGE), (Mushroom, RARE)],
+		(Grass, Warm) => vec![(JunglePlant, MEDIUM), (Tree, LARGE), (Flower, RARE)],
+		(WindSweptGrass, Cold) => vec![(Conifer, (0.1, 0.35))],
+		(WindSweptGrass, Temperate) => vec![(Conifer, LARGE), (Bush, RARE)],
+		(WindSweptGrass, Warm) => vec![(JunglePlant, LARGE)],
+		(Dirt, Warm) => vec![(Cactus, MEDIUM), (Rock, RARE)],
+		(Dirt, _) => vec![(Rock, MEDIUM), (Mushroom, RARE)],
+		(Stone, Cold) => vec![(Rock, MEDIUM), (SilverOre, LARGE), (IronOre, RARE)],
+		(Stone, Temperate) => vec![(Rock, MEDIUM), (IronOre, LARGE), (CopperOre, RARE)],
+		(Stone, Warm) => vec![(Rock, MEDIUM), (CopperOre, LARGE), (IronOre, RARE)],
+	}
+}
@@ -0,0 +1,98 @@
+use {
+	super::layer::{
+		Depth,
+		Layer,
+	},
+	crate::{
+		tile::{
+			GroundType,
+			ResourceType,
+		},
+		CHUNK_X,
+		CHUNK_Y,
+	},
+	worldgen::{
+		constraint,
+		noise::perlin::PerlinNoise,
+		noisemap::{
+			NoiseMap,
+			NoiseMapGenerator,
+			Seed,
+			Size,
+			Step,
+		},
+		world::{
+			tile::{
+				Constraint,
+				ConstraintType,
+			},
+			Tile,
+			World as WorldMaker,
+		},
+	},
+};
+
+// Bands of the cave map. Above WALLS is solid rock, the lowest hollows hold water.
+const POOLS: f64 = -0.55;
+const MUD: f64 = -0.35;
+const WALLS: f64 = 0.1;
+// Bands of the ore map, within walls
+const SILVER: f64 = 0.6;
+const IRON: f64 = 0.4;
+const COPPER: f64 = -0.45;
+
+// Caves are dark whatever the time, columns as in `TimeOfDay::tint`
+pub const LIGHT: [[f32; 4]; 4] = [
+	[0.45, 0.0, 0.0, 0.0],
+	[0.0, 0.45, 0.0, 0.0],
+	[0.0, 0.0, 0.55, 0.0],
+	[0.0, 0.0, 0.0, 1.0],
+];
+
+// Open caverns between rock walls, with ore seams through the rock. Each depth has its own noise.
+pub fn layer(resourceseed: &str, depth: Depth) -> Layer {
+	let noise = PerlinNoise::new();
+
+	let cavemap = NoiseMap::new(noise)
+		.set(Seed::of((resourceseed, "cave", depth)))
+		.set(Step::of(0.12, 0.12));
+
+	let oremap = NoiseMap::new(noise)
+		.set(Seed::of((resourceseed, "ore", depth)))
+		.set(Step::of(0.2, -0.2));
+
+	macro_rules! tile {
+		($tile:expr, cave > $low:expr) => {
+			Tile::new($tile).when(constraint!(Box::new(cavemap), > $low))
+		};
+		($tile:expr, cave < $high:expr) => {
+			Tile::new($tile).when(constraint!(Box::new(cavemap), < $high))
+		};
+	}
+
+	let groundmaker = WorldMaker::new()
+		.set(Size::of(CHUNK_X, CHUNK_Y))
+		.add(tile!(GroundType::Water, cave < POOLS))
+		.add(tile!(GroundType::Dirt, cave < MUD))
+		.add(Tile::new(GroundType::Stone)); // Default stone
+
+	let resourcemaker = WorldMaker::new()
+		.set(Size::of(CHUNK_X, CHUNK_Y))
+		.add(
+			tile!(ResourceType::SilverOre, cave > WALLS)
+				.when(constraint!(Box::new(oremap), > SILVER)),
+		)
+		.add(tile!(ResourceType::IronOre, cave > WALLS).when(constraint!(Box::new(oremap), > IRON)))
+		.add(
+			tile!(ResourceType::CopperOre, cave > WALLS)
+				.when(constraint!(Box::new(oremap), < COPPER)),
+		)
+		.add(tile!(ResourceType::Rock, cave > WALLS))
+		.add(
+			tile!(ResourceType::Mushroom, cave > MUD)
+				.when(constraint!(Box::new(cavemap), < MUD + 0.05)),
+		)
+		.add(Tile::new(ResourceType::None));
+
+	Layer::new(groundmaker, resourcemaker)
+}
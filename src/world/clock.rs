@@ -0,0 +1,104 @@
+use {
+	super::season::{
+		Season,
+		DEFAULT_SEASON_DAYS,
+	},
+	crate::TICK_LEN,
+	serde::{
+		Deserialize,
+		Serialize,
+	},
+};
+
+pub const DEFAULT_DAY_MINUTES: u64 = 10;
+const START_OF_GAME: f32 = 0.3; // Of a day, just after dawn
+const NIGHT_LIGHT: f32 = 0.35;
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Phase {
+	Night,
+	Dawn,
+	Day,
+	Dusk,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeOfDay {
+	pub day: u64,
+	pub fraction: f32, // 0.0 is midnight, 0.5 noon
+	pub season: Season,
+}
+impl TimeOfDay {
+	pub fn phase(&self) -> Phase {
+		match self.fraction {
+			fraction if fraction < 0.2 => Phase::Night,
+			fraction if fraction < 0.3 => Phase::Dawn,
+			fraction if fraction < 0.7 => Phase::Day,
+			fraction if fraction < 0.8 => Phase::Dusk,
+			_ => Phase::Night,
+		}
+	}
+	// From `NIGHT_LIGHT` to fully lit, fading over dawn and dusk
+	pub fn light(&self) -> f32 {
+		let fade = match self.phase() {
+			Phase::Night => 0.0,
+			Phase::Dawn => (self.fraction - 0.2) / 0.1,
+			Phase::Day => 1.0,
+			Phase::Dusk => (0.8 - self.fraction) / 0.1,
+		};
+		NIGHT_LIGHT + (1.0 - NIGHT_LIGHT) * fade
+	}
+	pub fn hours_minutes(&self) -> (u32, u32) {
+		let minutes = (self.fraction * 24.0 * 60.0) as u32;
+		(minutes / 60, minutes % 60)
+	}
+	// Darkens the scene, keeping some blue so nights aren't just grey
+	pub fn tint(&self) -> [[f32; 4]; 4] {
+		let light = self.light();
+		let blue = light + (1.0 - light) * 0.3;
+		[
+			[light, 0.0, 0.0, 0.0],
+			[0.0, light, 0.0, 0.0],
+			[0.0, 0.0, blue, 0.0],
+			[0.0, 0.0, 0.0, 1.0],
+		]
+	}
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Clock {
+	pub day_length: u64, // Ticks
+	#[serde(default = "default_season_days")]
+	pub season_days: u64,
+	#[serde(default)]
+	pub forced_season: Option<Season>, // For testing, stays this season whatever the day
+}
+impl Default for Clock {
+	fn default() -> Self {
+		Self::from_minutes(DEFAULT_DAY_MINUTES)
+	}
+}
+fn default_season_days() -> u64 {
+	DEFAULT_SEASON_DAYS
+}
+impl Clock {
+	pub fn from_minutes(minutes: u64) -> Self {
+		Self {
+			day_length: (minutes * 60 * 1000 / TICK_LEN).max(1),
+			season_days: DEFAULT_SEASON_DAYS,
+			forced_season: None,
+		}
+	}
+	pub fn time_at(&self, tick: u64) -> TimeOfDay {
+		let start = (self.day_length as f32 * START_OF_GAME) as u64;
+		let tick = tick + start;
+		let day = tick / self.day_length;
+		TimeOfDay {
+			day,
+			fraction: (tick % self.day_length) as f32 / self.day_length as f32,
+			season: self
+				.forced_season
+				.unwrap_or_else(|| Season::of_day(day, self.season_days)),
+		}
+	}
+}
@@ -0,0 +1,84 @@
+use {
+	super::tile::TilePos,
+	crate::{
+		tile::ResourceType,
+		CHUNK_X,
+		CHUNK_Y,
+	},
+	std::collections::BTreeMap,
+};
+
+pub const GROWTH_EVERY: u64 = 10; // Ticks between growth updates, :. once a second
+pub const SPREAD_CHANCE: f32 = 1.0 / 400.0; // Per flower per update, in normal weather
+const ACTIVE_RADIUS: i64 = 2; // Chunks around the player that count as visited
+const VISIT_MEMORY: u64 = 3000; // Ticks a chunk keeps growing once the player leaves, :. 5 minutes
+
+// Plants partway through growing back. Only chunks the player has been near recently grow, so
+// far off ones cost nothing and pick up where they left off when revisited.
+#[derive(Clone, Debug, Default)]
+pub struct Growth {
+	growing: BTreeMap<(i64, i64), u64>, // Ticks of growth towards the next stage
+	visited: BTreeMap<(i64, i64), u64>, // Chunk, last tick near the player
+}
+impl Growth {
+	// Called whenever a tile's resource changes, restarting its growth
+	pub fn plant(&mut self, pos: &TilePos, resource: &ResourceType) {
+		if resource.grows_into().is_some() {
+			self.growing.insert((pos.x, pos.y), 0);
+		} else {
+			self.growing.remove(&(pos.x, pos.y));
+		}
+	}
+	pub fn visit(&mut self, player: &TilePos, tick: u64) {
+		let (chunk_x, chunk_y) = (player.x.div_euclid(CHUNK_X), player.y.div_euclid(CHUNK_Y));
+		for x in chunk_x - ACTIVE_RADIUS..=chunk_x + ACTIVE_RADIUS {
+			for y in chunk_y - ACTIVE_RADIUS..=chunk_y + ACTIVE_RADIUS {
+				self.visited.insert((x, y), tick);
+			}
+		}
+		self.visited
+			.retain(|_, visited| *visited + VISIT_MEMORY >= tick);
+	}
+	pub fn active_chunks(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+		self.visited.keys().copied()
+	}
+	// Whether `pos` is in a chunk that grows, and so is loaded
+	pub fn is_active(&self, pos: &TilePos) -> bool {
+		self.visited
+			.contains_key(&(pos.x.div_euclid(CHUNK_X), pos.y.div_euclid(CHUNK_Y)))
+	}
+	// Adds growth to active plants, returning those ready for their next stage
+	pub fn grow(
+		&mut self,
+		resource_at: impl Fn(&TilePos) -> Option<ResourceType>,
+		rate_at: impl Fn(&TilePos) -> f32,
+	) -> Vec<(TilePos, ResourceType)> {
+		let visited = &self.visited;
+		let mut grown = vec![];
+		for (pos, progress) in self.growing.iter_mut() {
+			let pos = TilePos::from(*pos);
+			if !visited.contains_key(&(pos.x.div_euclid(CHUNK_X), pos.y.div_euclid(CHUNK_Y))) {
+				continue;
+			}
+			if let Some((next, needed)) =
+				resource_at(&pos).and_then(|resource| resource.grows_into())
+			{
+				*progress += (GROWTH_EVERY as f32 * rate_at(&pos)) as u64;
+				if *progress >= needed {
+					grown.push((pos, next));
+				}
+			}
+		}
+		grown
+	}
+	pub fn growing(&self) -> &BTreeMap<(i64, i64), u64> {
+		&self.growing
+	}
+	pub fn visited(&self) -> &BTreeMap<(i64, i64), u64> {
+		&self.visited
+	}
+	// As it was when saved
+	pub fn restore(growing: BTreeMap<(i64, i64), u64>, visited: BTreeMap<(i64, i64), u64>) -> Self {
+		Self { growing, visited }
+	}
+}
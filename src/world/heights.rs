@@ -0,0 +1,39 @@
+use {
+	super::tile::TilePos,
+	crate::{
+		CHUNK_X,
+		CHUNK_Y,
+	},
+	std::collections::HashMap,
+	worldgen::{
+		noise::perlin::PerlinNoise,
+		noisemap::{
+			NoiseMap,
+			NoiseMapGeneratorBase,
+			Size,
+		},
+	},
+};
+
+// The ground map sampled a tile at a time, for passes run after generation
+pub struct Heights {
+	map: NoiseMap<PerlinNoise>,
+	chunks: HashMap<(i64, i64), Vec<Vec<f64>>>,
+}
+impl Heights {
+	pub fn new(map: NoiseMap<PerlinNoise>) -> Self {
+		Self {
+			map,
+			chunks: HashMap::new(),
+		}
+	}
+	// Matches the ground generated at `pos`
+	pub fn at(&mut self, pos: &TilePos) -> f64 {
+		let map = self.map;
+		self.chunks
+			.entry((pos.x.div_euclid(CHUNK_X), pos.y.div_euclid(CHUNK_Y)))
+			.or_insert_with_key(|(x, y)| {
+				map.generate_sized_chunk(Size::of(CHUNK_X, CHUNK_Y), *x, *y)
+			})[pos.x.rem_euclid(CHUNK_X) as usize][pos.y.rem_euclid(CHUNK_Y) as usize]
+	}
+}
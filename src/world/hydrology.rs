@@ -0,0 +1,145 @@
+use {
+	super::{
+		biome::SEA_LEVEL,
+		heights::Heights,
+		tile::TilePos,
+	},
+	crate::{
+		tile::{
+			GroundType,
+			ResourceType,
+		},
+		CHUNK_X,
+		CHUNK_Y,
+	},
+	std::{
+		collections::{
+			hash_map::DefaultHasher,
+			HashMap,
+			HashSet,
+		},
+		hash::{
+			Hash,
+			Hasher,
+		},
+	},
+};
+
+pub const BASIN_CHUNKS: i64 = 4; // At most one river starts in each, :. 16x16 tiles
+const SOURCE_CHANCE: u32 = 2; // 1 in
+const SOURCE_TRIES: usize = 4; // Rivers start from the highest of these points in their basin
+const SOURCE_HEIGHT: f64 = 0.1; // Of the ground map, :. up in the hills
+const MAX_LENGTH: usize = 96; // Tiles, a river that hasn't reached water by then pools
+const LAKE_RADIUS: i64 = 2;
+const SIDES: [(i64, i64); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+// Rivers run downhill from sources picked by the seed until they reach water, pooling into a
+// lake where there's no lower ground. Each only depends on the seed and ground heights, so a
+// chunk can be carved without the chunks around it existing.
+pub struct Hydrology {
+	seed: u64,
+	rivers: HashMap<(i64, i64), Vec<TilePos>>, // Water carved by the river from each basin
+}
+impl Hydrology {
+	pub fn new(seed: u64) -> Self {
+		Self {
+			seed,
+			rivers: HashMap::new(),
+		}
+	}
+	// Replaces the ground along rivers with water, and ground beside any water with sand
+	pub fn carve(
+		&mut self,
+		chunk: (i64, i64),
+		tiles: &mut [Vec<(GroundType, ResourceType)>],
+		heights: &mut Heights,
+	) {
+		let origin = TilePos::from((chunk.0 * CHUNK_X, chunk.1 * CHUNK_Y));
+		// Water just outside the chunk still makes shore inside it
+		let (min, max) = (origin - &(1, 1).into(), origin + &(CHUNK_X, CHUNK_Y).into());
+		let reach = MAX_LENGTH as i64 + LAKE_RADIUS;
+		let (basin_x, basin_y) = (BASIN_CHUNKS * CHUNK_X, BASIN_CHUNKS * CHUNK_Y);
+		let mut water: HashSet<TilePos> = HashSet::new();
+		for x in (min.x - reach).div_euclid(basin_x)..=(max.x + reach).div_euclid(basin_x) {
+			for y in (min.y - reach).div_euclid(basin_y)..=(max.y + reach).div_euclid(basin_y) {
+				water.extend(self.river((x, y), heights).iter().filter(|pos| {
+					pos.x >= min.x && pos.x <= max.x && pos.y >= min.y && pos.y <= max.y
+				}));
+			}
+		}
+		for (x, column) in tiles.iter_mut().enumerate() {
+			for (y, tile) in column.iter_mut().enumerate() {
+				let pos = origin + &(x as i64, y as i64).into();
+				if water.contains(&pos) {
+					*tile = (GroundType::Water, ResourceType::None);
+				} else if tile.0 != GroundType::Water
+					&& tile.0 != GroundType::Stone
+					&& SIDES.iter().any(|side| {
+						let side = pos + &(*side).into();
+						water.contains(&side) || heights.at(&side) < SEA_LEVEL
+					}) {
+					*tile = (GroundType::Sand, ResourceType::None);
+				}
+			}
+		}
+	}
+	pub(super) fn river(&mut self, basin: (i64, i64), heights: &mut Heights) -> &Vec<TilePos> {
+		if !self.rivers.contains_key(&basin) {
+			let river = self.trace(basin, heights);
+			self.rivers.insert(basin, river);
+		}
+		&self.rivers[&basin]
+	}
+	fn trace(&self, basin: (i64, i64), heights: &mut Heights) -> Vec<TilePos> {
+		let mut hasher = DefaultHasher::new();
+		(self.seed, basin).hash(&mut hasher);
+		let rng = fastrand::Rng::with_seed(hasher.finish());
+		if rng.u32(0..SOURCE_CHANCE) != 0 {
+			return vec![];
+		}
+		let (size_x, size_y) = (BASIN_CHUNKS * CHUNK_X, BASIN_CHUNKS * CHUNK_Y);
+		let (height, source) = (0..SOURCE_TRIES)
+			.map(|_| {
+				let pos = TilePos::from((
+					basin.0 * size_x + rng.i64(0..size_x),
+					basin.1 * size_y + rng.i64(0..size_y),
+				));
+				(heights.at(&pos), pos)
+			})
+			.max_by(|a, b| a.0.total_cmp(&b.0))
+			.expect("SOURCE_TRIES is more than 0");
+		if height < SOURCE_HEIGHT {
+			return vec![];
+		}
+		let mut river = vec![source];
+		let mut pos = source;
+		while river.len() < MAX_LENGTH {
+			let height = heights.at(&pos);
+			if height < SEA_LEVEL {
+				return river;
+			}
+			let (lowest, next) = SIDES
+				.map(|side| {
+					let side = pos + &side.into();
+					(heights.at(&side), side)
+				})
+				.into_iter()
+				.min_by(|a, b| a.0.total_cmp(&b.0))
+				.expect("there are 4 sides");
+			if lowest >= height {
+				break;
+			}
+			pos = next;
+			river.push(pos);
+		}
+		// Nowhere lower to go, so it pools
+		for x in -LAKE_RADIUS..=LAKE_RADIUS {
+			for y in -LAKE_RADIUS..=LAKE_RADIUS {
+				if x * x + y * y <= LAKE_RADIUS * LAKE_RADIUS + 1 {
+					river.push(pos + &(x, y).into());
+				}
+			}
+		}
+		river
+	}
+}
@@ -0,0 +1,29 @@
+use {
+	super::{
+		layer::Depth,
+		structures::StructureKind,
+		tile::TilePos,
+	},
+	crate::tile::{
+		GroundType,
+		ResourceType,
+	},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Entity {
+	Player,
+	Structure(StructureKind), // Part of one
+	Waypoint,
+}
+
+// Everything known about one tile, from `World::inspect`
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileInfo {
+	pub pos: TilePos,
+	pub depth: Depth,
+	pub explored: bool,
+	pub tile: Option<(GroundType, ResourceType)>, // None if its chunk couldn't generate
+	pub walkable: bool,
+	pub entities: Vec<Entity>,
+}
@@ -0,0 +1,62 @@
+use {
+	super::{
+		vision::Explored,
+		Chunks,
+	},
+	crate::tile::{
+		GroundType,
+		ResourceType,
+	},
+	std::collections::{
+		BTreeMap,
+		BTreeSet,
+		HashSet,
+	},
+	worldgen::world::World as WorldMaker,
+};
+
+pub type Depth = u32; // Layers below the surface count up from it
+pub const SURFACE: Depth = 0;
+pub const CAVES: Depth = 1; // Where cave entrances lead
+
+// One depth of the world, generated from its own noise
+pub struct Layer {
+	groundmaker: WorldMaker<GroundType>,
+	resourcemaker: WorldMaker<ResourceType>,
+	pub chunks: Chunks,
+	pub edits: BTreeMap<(i64, i64), (GroundType, ResourceType)>, // Tiles changed since generation
+	pub failed_chunks: HashSet<(i64, i64)>, // Not retried, so each is only reported once
+	pub explored: Explored,
+	pub waypoints: BTreeSet<(i64, i64)>, // Tiles marked by the player
+}
+impl Layer {
+	pub fn new(
+		groundmaker: WorldMaker<GroundType>,
+		resourcemaker: WorldMaker<ResourceType>,
+	) -> Self {
+		Self {
+			groundmaker,
+			resourcemaker,
+			chunks: Chunks::default(),
+			edits: BTreeMap::new(),
+			failed_chunks: HashSet::new(),
+			explored: Explored::default(),
+			waypoints: BTreeSet::new(),
+		}
+	}
+	// Before any passes that shape it further
+	pub fn generate(
+		&self,
+		chunk_x: i64,
+		chunk_y: i64,
+	) -> Option<Vec<Vec<(GroundType, ResourceType)>>> {
+		Some(
+			self.groundmaker
+				.generate(chunk_x, chunk_y)?
+				.into_iter()
+				.zip(self.resourcemaker.generate(chunk_x, chunk_y)?)
+				.map(|(ground, resource)| ground.into_iter().zip(resource).collect())
+				.collect(),
+		)
+	}
+}
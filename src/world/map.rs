@@ -0,0 +1,180 @@
+use {
+	super::{
+		tile::{
+			PixelPos,
+			TilePos,
+		},
+		Chunks,
+	},
+	crate::{
+		tile::GroundType,
+		Error,
+	},
+	crow::{
+		image::{
+			Rgba,
+			RgbaImage,
+		},
+		Context,
+		DrawConfig,
+		Texture,
+		WindowSurface,
+	},
+	std::sync::Arc,
+};
+
+pub const MINIMAP_SIZE: i64 = 48; // Tiles across, centred on the player
+pub const MINIMAP_SCALE: u32 = 3; // Screen pixels per tile
+pub const MINIMAP_MARGIN: i64 = 16; // Pixels from the top right corner
+pub const MAP_ZOOM: i64 = 16; // The full map covers this many views across
+
+const FOG: Rgba<u8> = Rgba([20, 20, 24, 255]); // Not yet explored
+const BORDER: (f32, f32, f32, f32) = (0.9, 0.9, 0.9, 1.0);
+
+// Every ground type needs a colour, so there's no catch-all
+pub fn colour(ground: &GroundType) -> Rgba<u8> {
+	use GroundType::*;
+	Rgba(match ground {
+		Water => [52, 101, 164, 255],
+		PlainGrass => [122, 171, 82, 255],
+		Grass => [84, 146, 60, 255],
+		WindSweptGrass => [152, 168, 92, 255],
+		Dirt => [139, 104, 70, 255],
+		Stone => [128, 128, 128, 255],
+		Sand => [221, 201, 142, 255],
+		Bricks => [158, 82, 62, 255],
+	})
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Marker {
+	Player,
+	PointOfInterest, // Structures, and the ladders under cave entrances
+	Waypoint,        // Placed by the player
+}
+impl Marker {
+	fn colour(&self) -> Rgba<u8> {
+		use Marker::*;
+		Rgba(match self {
+			Player => [255, 255, 255, 255],
+			PointOfInterest => [250, 210, 60, 255],
+			Waypoint => [230, 50, 50, 255],
+		})
+	}
+	// Offsets from the marked tile, bigger than a pixel so they stand out
+	fn shape(&self) -> &'static [(i64, i64)] {
+		use Marker::*;
+		match self {
+			Player => &[(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1)],
+			PointOfInterest => &[(0, 0), (1, 1), (-1, 1), (1, -1), (-1, -1)],
+			Waypoint => &[
+				(0, 0),
+				(1, 0),
+				(-1, 0),
+				(0, 1),
+				(0, -1),
+				(1, 1),
+				(-1, 1),
+				(1, -1),
+				(-1, -1),
+			],
+		}
+	}
+	// Points of interest are found by exploring
+	fn shown_in_fog(&self) -> bool {
+		!matches!(self, Marker::PointOfInterest)
+	}
+}
+
+// A region of one layer at a pixel per tile, north up
+#[derive(Clone)]
+pub struct MapImage {
+	pub pos: TilePos, // South west corner
+	pub image: RgbaImage,
+}
+impl MapImage {
+	// Tiles that aren't explored, or haven't been generated, are fogged over. Markers go on
+	// top in the order given.
+	pub fn render(
+		chunks: &Chunks,
+		explored: impl Fn(&TilePos) -> bool,
+		pos: TilePos,
+		size: TilePos,
+		markers: &[(TilePos, Marker)],
+	) -> Self {
+		let mut map = Self {
+			pos,
+			image: RgbaImage::from_pixel(size.x.max(0) as u32, size.y.max(0) as u32, FOG),
+		};
+		for x in 0..size.x {
+			for y in 0..size.y {
+				let tile = pos + &(x, y).into();
+				if let (true, Some(ground)) = (explored(&tile), chunks.ground_at(&tile)) {
+					map.set(&tile, colour(ground));
+				}
+			}
+		}
+		for (tile, marker) in markers {
+			if marker.shown_in_fog() || explored(tile) {
+				for offset in marker.shape() {
+					map.set(&(*tile + &(*offset).into()), marker.colour());
+				}
+			}
+		}
+		map
+	}
+	pub fn size(&self) -> TilePos {
+		(self.image.width() as i64, self.image.height() as i64).into()
+	}
+	pub fn is_empty(&self) -> bool {
+		self.image.width() == 0 || self.image.height() == 0
+	}
+	// Anything outside the map is left off
+	fn set(&mut self, tile: &TilePos, colour: Rgba<u8>) {
+		let (offset, size) = (*tile - &self.pos, self.size());
+		if offset.x >= 0 && offset.y >= 0 && offset.x < size.x && offset.y < size.y {
+			self.image
+				.put_pixel(offset.x as u32, (size.y - 1 - offset.y) as u32, colour);
+		}
+	}
+}
+
+// The last map drawn, only uploaded again once the tick thread sends a different one
+#[derive(Default)]
+pub struct MapTexture(Option<(Arc<MapImage>, Texture)>);
+impl MapTexture {
+	// With its lower left corner at `pos`, outlined
+	pub fn draw(
+		&mut self,
+		ctx: &mut Context,
+		surface: &mut WindowSurface,
+		map: &Arc<MapImage>,
+		pos: PixelPos,
+		scale: u32,
+	) -> Result<(), Error> {
+		if map.is_empty() {
+			return Ok(()); // No view yet
+		}
+		let texture = match &self.0 {
+			Some((drawn, texture)) if Arc::ptr_eq(drawn, map) => texture,
+			_ => {
+				let texture = Texture::from_image(ctx, map.image.clone())
+					.map_err(|error| Error::Upload(error.to_string()))?;
+				&self.0.insert((map.clone(), texture)).1
+			}
+		};
+		let (width, height) = map.image.dimensions();
+		let corner = pos + &((width * scale) as i64, (height * scale) as i64).into();
+		ctx.draw(
+			surface,
+			texture,
+			pos.try_into()?,
+			&DrawConfig {
+				scale: (scale, scale),
+				..DrawConfig::default()
+			},
+		);
+		ctx.debug_rectangle(surface, pos.try_into()?, corner.try_into()?, BORDER);
+		Ok(())
+	}
+}
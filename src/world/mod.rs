@@ -1,22 +1,72 @@
 use {
-	self::tile::{
-		PixelPos,
-		TilePos,
+	self::{
+		autotile::Transition,
+		biome::{
+			Climate,
+			GROUND_HEIGHTS,
+		},
+		clock::{
+			Clock,
+			TimeOfDay,
+		},
+		growth::{
+			Growth,
+			GROWTH_EVERY,
+			SPREAD_CHANCE,
+		},
+		heights::Heights,
+		hydrology::Hydrology,
+		inspect::{
+			Entity,
+			TileInfo,
+		},
+		layer::{
+			Depth,
+			Layer,
+			CAVES,
+			SURFACE,
+		},
+		map::{
+			MapImage,
+			Marker,
+			MAP_ZOOM,
+			MINIMAP_SIZE,
+		},
+		season::Season,
+		structures::{
+			Structure,
+			Structures,
+		},
+		tile::TilePos,
+		vision::{
+			Explored,
+			VISION_RADIUS,
+		},
+		weather::{
+			Weather,
+			WeatherMap,
+		},
 	},
 	crate::{
 		assets::Player,
-		atlas::Atlas,
-		frame_manager::draw::Draw,
+		events::GameEvent,
 		tile::*,
 		Error,
 		CHUNK_X,
 		CHUNK_Y,
 	},
-	crow::{
-		Context,
-		WindowSurface,
+	std::{
+		collections::{
+			hash_map::DefaultHasher,
+			BTreeMap,
+			HashMap,
+		},
+		hash::{
+			Hash,
+			Hasher,
+		},
+		sync::Arc,
 	},
-	std::collections::HashMap,
 	worldgen::{
 		constraint,
 		noise::perlin::PerlinNoise,
@@ -38,20 +88,116 @@ use {
 	},
 };
 
+pub mod autotile;
+pub mod biome;
+pub mod cave;
+pub mod clock;
+pub mod growth;
+pub mod heights;
+pub mod hydrology;
+pub mod inspect;
+pub mod layer;
+pub mod map;
 pub mod pixel_pos;
+pub mod save;
+pub mod season;
+pub mod snapshot;
+pub mod structures;
 pub mod tile;
 pub mod tile_pos;
+pub mod vision;
+pub mod weather;
+
+pub use snapshot::WorldSnapshot;
 
 #[derive(Clone)]
 pub struct Chunk {
 	tiles: Vec<Vec<(GroundType, ResourceType)>>,
 }
 
+// Chunks are shared with snapshots, so copying a region doesn't copy tiles
+#[derive(Clone, Default)]
+pub struct Chunks(HashMap<i64, HashMap<i64, Arc<Chunk>>>);
+impl Chunks {
+	pub fn get(&self, chunk_x: i64, chunk_y: i64) -> Option<&Arc<Chunk>> {
+		self.0.get(&chunk_x)?.get(&chunk_y)
+	}
+	pub fn insert(&mut self, chunk_x: i64, chunk_y: i64, chunk: Arc<Chunk>) {
+		self.0.entry(chunk_x).or_default().insert(chunk_y, chunk);
+	}
+	// Copies the chunk first if a snapshot still holds it
+	pub fn tile_mut(&mut self, pos: &TilePos) -> Option<&mut (GroundType, ResourceType)> {
+		let chunk = self
+			.0
+			.get_mut(&pos.x.div_euclid(CHUNK_X))?
+			.get_mut(&pos.y.div_euclid(CHUNK_Y))?;
+		Arc::make_mut(chunk)
+			.tiles
+			.get_mut(pos.x.rem_euclid(CHUNK_X) as usize)?
+			.get_mut(pos.y.rem_euclid(CHUNK_Y) as usize)
+	}
+	pub fn tile_at(&self, pos: &TilePos) -> Option<&(GroundType, ResourceType)> {
+		let chunk = self.get(pos.x.div_euclid(CHUNK_X), pos.y.div_euclid(CHUNK_Y))?;
+		chunk
+			.tiles
+			.get(pos.x.rem_euclid(CHUNK_X) as usize)?
+			.get(pos.y.rem_euclid(CHUNK_Y) as usize)
+	}
+	pub fn ground_at(&self, pos: &TilePos) -> Option<&GroundType> {
+		self.tile_at(pos).map(|(ground, _)| ground)
+	}
+	pub fn transitions_at(&self, pos: &TilePos) -> Vec<Transition> {
+		match self.ground_at(pos) {
+			Some(ground) => {
+				let neighbours = autotile::neighbour_positions(pos)
+					.map(|neighbour| self.ground_at(&neighbour).cloned());
+				autotile::transitions(ground, &neighbours)
+			}
+			None => vec![],
+		}
+	}
+}
+
+// Chunk coordinates covering a board at `pos` of `size` tiles
+pub fn chunk_range(pos: TilePos, size: TilePos) -> impl Iterator<Item = (i64, i64)> {
+	let chunk_pos = pos / &(CHUNK_X, CHUNK_Y).into();
+	let chunk_size = size / &(CHUNK_X, CHUNK_Y).into();
+	(chunk_pos.x - 1..chunk_pos.x + chunk_size.x).flat_map(move |chunk_x| {
+		(chunk_pos.y - 1..chunk_pos.y + chunk_size.y).map(move |chunk_y| (chunk_x, chunk_y))
+	})
+}
+
+// Combines the seeds for anything random that isn't generation
+fn seed_of(ground_seed: &str, resource_seed: &str) -> u64 {
+	let mut hasher = DefaultHasher::new();
+	ground_seed.hash(&mut hasher);
+	resource_seed.hash(&mut hasher);
+	hasher.finish()
+}
+
+// With the depth, player position, size and `World::map_changes` it was made for
+type CachedMap = Option<((Depth, TilePos, TilePos, u64), Arc<MapImage>)>;
+
 pub struct World {
-	groundmaker: WorldMaker<GroundType>,
-	resourcemaker: WorldMaker<ResourceType>,
-	chunks: HashMap<i64, HashMap<i64, Chunk>>,
+	pub ground_seed: String,
+	pub resource_seed: String,
+	seed: u64,
+	layers: BTreeMap<Depth, Layer>,
+	pub depth: Depth, // Of the player
+	heights: Heights,
+	hydrology: Hydrology,
+	structures: Structures,
+	growth: Growth,
 	pub player: Player,
+	pub tick: u64, // Of the last tick run
+	pub clock: Clock,
+	weather: WeatherMap,
+	local_weather: Weather, // Last reported where the player is
+	pub view_size: TilePos,
+	events: Vec<GameEvent>,
+	map_changes: u64, // Counts anything that could change the map, so it's only made again then
+	minimap: CachedMap,
+	map: CachedMap,
 }
 impl World {
 	pub fn new(groundseed: &str, resourceseed: &str) -> Self {
@@ -65,12 +211,10 @@ impl World {
 			.set(Seed::of(resourceseed))
 			.set(Step::of(0.1, -0.1));
 
-		let height_water = (-1.5, -0.5);
-		let height_stillgrass = (-0.5, -0.4);
-		let height_grass = (-0.4, 0.1);
-		let height_windsweptgrass = (0.1, 0.4);
-		let height_dirt = (0.4, 1.0);
-		let height_stone = (1.0, 1.5);
+		// Large scale, so biomes span many chunks
+		let climatemap = NoiseMap::new(noise)
+			.set(Seed::of((resourceseed, "climate")))
+			.set(Step::of(0.02, 0.02));
 
 		macro_rules! tile {
 			($tile:expr, $height:expr) => {
@@ -78,123 +222,761 @@ impl World {
 					.when(constraint!(Box::new(groundmap), > $height.0))
 					.when(constraint!(Box::new(groundmap), < $height.1))
 			};
-			($tile:expr, $height:expr, $size:expr) => {
+			($tile:expr, $height:expr, $size:expr, $climate:expr) => {
 				tile!($tile, $height)
 					.when(constraint!(Box::new(resourcemap), > $size.0))
 					.when(constraint!(Box::new(resourcemap), < $size.1))
+					.when(constraint!(Box::new(climatemap), > $climate.0))
+					.when(constraint!(Box::new(climatemap), < $climate.1))
 			};
 		}
 
-		let groundmaker = {
-			use GroundType::*;
-			WorldMaker::new()
-				.set(Size::of(CHUNK_X as i64, CHUNK_Y as i64))
-				.add(tile!(Water, height_water))
-				.add(tile!(PlainGrass, height_stillgrass))
-				.add(tile!(Grass, height_grass))
-				.add(tile!(WindSweptGrass, height_windsweptgrass))
-				.add(tile!(Dirt, height_dirt))
-				.add(tile!(Stone, height_stone))
-				.add(Tile::new(Dirt)) // Default dirt
-		};
+		let groundmaker = GROUND_HEIGHTS
+			.iter()
+			.fold(
+				WorldMaker::new().set(Size::of(CHUNK_X, CHUNK_Y)),
+				|maker, (ground, heights)| maker.add(tile!(ground.clone(), heights)),
+			)
+			.add(Tile::new(GroundType::Dirt)); // Default dirt
 
-		let size_small = (0.0, 0.1);
-		let size_medium = (0.1, 0.2);
-		let size_large = (0.2, 0.35);
-
-		let resourcemaker = {
-			use ResourceType::*;
-			WorldMaker::new()
-				.set(Size::of(CHUNK_X as i64, CHUNK_Y as i64))
-				.add(tile!(Rock, (height_dirt.0, height_stone.1), size_medium))
-				.add(tile!(Bush, height_grass, size_medium))
-				.add(tile!(Tree, height_grass, size_large))
-				.add(tile!(Flower, height_stillgrass, size_small))
-				.add(Tile::new(None))
-		};
+		let mut resourcemaker = WorldMaker::new().set(Size::of(CHUNK_X, CHUNK_Y));
+		for (ground, heights) in GROUND_HEIGHTS.iter() {
+			for climate in Climate::ALL {
+				for (resource, size) in biome::spawn_table(ground, climate) {
+					resourcemaker =
+						resourcemaker.add(tile!(resource, heights, size, climate.band()));
+				}
+			}
+		}
+		let resourcemaker = resourcemaker.add(Tile::new(ResourceType::None));
 
 		Self {
-			groundmaker,
-			resourcemaker,
-			chunks: HashMap::new(),
+			ground_seed: groundseed.to_string(),
+			resource_seed: resourceseed.to_string(),
+			seed: seed_of(groundseed, resourceseed),
+			layers: BTreeMap::from([(SURFACE, Layer::new(groundmaker, resourcemaker))]),
+			depth: SURFACE,
+			heights: Heights::new(groundmap),
+			hydrology: Hydrology::new(seed_of(groundseed, resourceseed)),
+			structures: Structures::new(seed_of(groundseed, resourceseed)),
+			growth: Growth::default(),
 			player: Player::new(),
+			tick: 0,
+			clock: Clock::default(),
+			weather: WeatherMap::new(seed_of(groundseed, resourceseed)),
+			local_weather: Weather::Clear,
+			view_size: (0, 0).into(),
+			events: vec![],
+			map_changes: 0,
+			minimap: None,
+			map: None,
 		}
 	}
-	pub fn load(&mut self, pos: TilePos, size: TilePos) {
-		// Measure in chunks
-		let chunk_pos = pos / &(CHUNK_X, CHUNK_Y).into();
-		let chunk_size = size / &(CHUNK_X, CHUNK_Y).into();
-
-		for chunk_x in chunk_pos.x - 1..chunk_pos.x + chunk_size.x {
-			let mut row: HashMap<i64, Chunk> = self
-				.chunks
-				.get(&chunk_x)
-				.map(|chunk| chunk.clone())
-				.unwrap_or(HashMap::new());
-			for chunk_y in chunk_pos.y - 1..chunk_pos.y + chunk_size.y {
-				if !row.contains_key(&chunk_y) {
-					let tiles = self
-						.groundmaker
-						.generate(chunk_x, chunk_y)
-						.unwrap()
-						.into_iter()
-						.zip(self.resourcemaker.generate(chunk_x, chunk_y).unwrap())
-						.map(|(ground, resource)| ground.into_iter().zip(resource).collect())
-						.collect();
-					row.insert(chunk_y, Chunk { tiles });
-				}
+	pub fn time_of_day(&self) -> TimeOfDay {
+		self.clock.time_at(self.tick)
+	}
+	pub fn set_tick(&mut self, tick: u64) {
+		let before = self.time_of_day();
+		self.tick = tick;
+		let time = self.time_of_day();
+		if time.phase() != before.phase() {
+			self.events.push(GameEvent::PhaseChanged {
+				day: time.day,
+				phase: time.phase(),
+			});
+		}
+		if time.season != before.season {
+			self.events.push(GameEvent::SeasonChanged {
+				season: time.season,
+			});
+		}
+		let region = weather::region_of(self.player.get_position());
+		let weather = self.weather.at(region, tick, time.season);
+		if weather != self.local_weather {
+			self.local_weather = weather;
+			self.events
+				.push(GameEvent::WeatherChanged { region, weather });
+		}
+	}
+	pub fn season(&self) -> Season {
+		self.time_of_day().season
+	}
+	// The board drawn follows the player
+	pub fn view_position(&self) -> TilePos {
+		*self.player.get_position() - &(self.view_size / 4) // TODO: Define differently // I have no idea why this is 4 and not 2
+	}
+	// Chunks that fail to generate are left empty, their errors returned
+	pub fn load(&mut self, pos: TilePos, size: TilePos) -> Vec<Error> {
+		let mut errors = vec![];
+		for (chunk_x, chunk_y) in chunk_range(pos, size) {
+			if !self.layer().failed_chunks.contains(&(chunk_x, chunk_y)) {
+				errors.extend(self.load_chunk(self.depth, chunk_x, chunk_y).err());
+			}
+		}
+		errors
+	}
+	fn load_chunk(&mut self, depth: Depth, chunk_x: i64, chunk_y: i64) -> Result<(), Error> {
+		let error = Error::Generation {
+			depth,
+			chunk: (chunk_x, chunk_y),
+		};
+		let layer = self.layer_mut(depth);
+		if layer.chunks.get(chunk_x, chunk_y).is_some() {
+			return Ok(());
+		} else if layer.failed_chunks.contains(&(chunk_x, chunk_y)) {
+			return Err(error);
+		}
+		match self.generate(depth, chunk_x, chunk_y) {
+			Some(chunk) => {
+				self.layer_mut(depth)
+					.chunks
+					.insert(chunk_x, chunk_y, Arc::new(chunk));
+				self.map_changes += 1;
+				self.events.push(GameEvent::ChunkGenerated {
+					depth,
+					chunk: (chunk_x, chunk_y),
+				});
+				Ok(())
+			}
+			None => {
+				self.layer_mut(depth)
+					.failed_chunks
+					.insert((chunk_x, chunk_y));
+				Err(error)
+			}
+		}
+	}
+	// The one the player is on
+	fn layer(&self) -> &Layer {
+		&self.layers[&self.depth]
+	}
+	// Underground layers are made the first time they're needed
+	fn layer_mut(&mut self, depth: Depth) -> &mut Layer {
+		let resource_seed = &self.resource_seed;
+		self.layers
+			.entry(depth)
+			.or_insert_with(|| cave::layer(resource_seed, depth))
+	}
+	pub fn load_view(&mut self) -> Vec<Error> {
+		self.load(self.view_position(), self.view_size)
+	}
+	// Explores what the player can see from where they are. Generates what's in range first, so
+	// it doesn't depend on what's been drawn.
+	pub fn look(&mut self) -> Vec<Error> {
+		let radius = (VISION_RADIUS, VISION_RADIUS).into();
+		let errors = self.load(
+			*self.player.get_position() - &radius,
+			radius * &(2, 2).into(),
+		);
+		let seen = vision::visible(self.player.get_position(), VISION_RADIUS, |pos| {
+			self.tile_at(pos)
+				.is_some_and(|(_, resource)| resource.blocks_sight())
+		});
+		let depth = self.depth;
+		let explored = &mut self.layer_mut(depth).explored;
+		let mut new = false;
+		for pos in &seen {
+			new |= explored.insert(pos);
+		}
+		if new {
+			self.map_changes += 1;
+		}
+		errors
+	}
+	fn generate(&mut self, depth: Depth, chunk_x: i64, chunk_y: i64) -> Option<Chunk> {
+		let mut tiles = self.layer_mut(depth).generate(chunk_x, chunk_y)?;
+		if depth == SURFACE {
+			self.hydrology
+				.carve((chunk_x, chunk_y), &mut tiles, &mut self.heights);
+			self.structures
+				.stamp((chunk_x, chunk_y), &mut tiles, &mut self.heights);
+		} else if depth == CAVES {
+			self.structures
+				.stamp_exits((chunk_x, chunk_y), &mut tiles, &mut self.heights);
+		}
+		// Only made before the chunk was when continuing a save
+		let edits = &self.layer_mut(depth).edits;
+		for (col, column) in tiles.iter_mut().enumerate() {
+			let x = chunk_x * CHUNK_X + col as i64;
+			let y = chunk_y * CHUNK_Y;
+			for ((_, edit_y), edit) in edits.range((x, y)..(x, y + CHUNK_Y)) {
+				column[(edit_y - y) as usize] = edit.clone();
 			}
-			self.chunks.insert(chunk_x, row);
 		}
+		Some(Chunk { tiles })
 	}
-	pub fn draw(
+	pub fn ground_at(&self, pos: &TilePos) -> Option<&GroundType> {
+		self.layer().chunks.ground_at(pos)
+	}
+	pub fn transitions_at(&self, pos: &TilePos) -> Vec<Transition> {
+		self.layer().chunks.transitions_at(pos)
+	}
+	pub fn tile_at(&self, pos: &TilePos) -> Option<&(GroundType, ResourceType)> {
+		self.layer().chunks.tile_at(pos)
+	}
+	// Structures overlapping the board at `pos` of `size` tiles, whether generated yet or not
+	pub fn structures_within(&mut self, pos: TilePos, size: TilePos) -> Vec<Structure> {
+		self.structures.within(pos, size, &mut self.heights)
+	}
+	pub fn structure_at(&mut self, pos: &TilePos) -> Option<Structure> {
+		self.structures.at(pos, &mut self.heights)
+	}
+	// Generates the chunk first if needed, so edits don't depend on what's been seen
+	pub fn set_tile(
 		&mut self,
-		ctx: &mut Context,
-		surface: &mut WindowSurface,
-		atlas: &Atlas,
 		pos: TilePos,
-		size: TilePos,
-		offset: PixelPos,
-		frame: usize,
+		ground: GroundType,
+		resource: ResourceType,
+	) -> Result<(), Error> {
+		self.set_tile_at(self.depth, pos, ground, resource)
+	}
+	fn set_tile_at(
+		&mut self,
+		depth: Depth,
+		pos: TilePos,
+		ground: GroundType,
+		resource: ResourceType,
 	) -> Result<(), Error> {
-		// Measure in chunks
-		let chunk_pos = pos / &(CHUNK_X, CHUNK_Y).into();
-		let chunk_size = size / &(CHUNK_X, CHUNK_Y).into();
-
-		println!("Board: {:?}, Player: {:?}", pos, self.player.get_position());
-
-		for chunk_x in chunk_pos.x - 1..chunk_pos.x + chunk_size.x {
-			let row = self.chunks.get(&chunk_x).unwrap();
-			for chunk_y in chunk_pos.y - 1..chunk_pos.y + chunk_size.y {
-				let chunk = row.get(&chunk_y).unwrap();
-				chunk.tiles.iter().enumerate().for_each(|(col, tiles)| {
-					tiles
-						.iter()
-						.enumerate()
-						.for_each(|(row, (ground, resource))| {
-							let chunk_pos: TilePos =
-								TilePos::from((chunk_x, chunk_y)) * &(CHUNK_X, CHUNK_Y).into();
-							let tile_pos = chunk_pos
-								+ &(col.try_into().unwrap(), row.try_into().unwrap()).into();
-							let rel_pos = tile_pos - &pos;
-							let pixel_pos = PixelPos::from(rel_pos) + &offset;
-							ground
-								.draw(ctx, surface, pixel_pos.clone(), atlas, frame)
-								.unwrap();
-							resource
-								.draw(ctx, surface, pixel_pos, atlas, frame)
-								.unwrap();
-							if &tile_pos == self.player.get_position() {
-								// TODO: Do this more generically
-								self.player
-									.draw(ctx, surface, pixel_pos, atlas, frame)
-									.unwrap();
-							}
-							// TODO: Handle
-						})
+		self.load_chunk(depth, pos.x.div_euclid(CHUNK_X), pos.y.div_euclid(CHUNK_Y))?;
+		let layer = self.layer_mut(depth);
+		if let Some(tile) = layer.chunks.tile_mut(&pos) {
+			*tile = (ground.clone(), resource.clone());
+		}
+		layer
+			.edits
+			.insert((pos.x, pos.y), (ground.clone(), resource.clone()));
+		self.map_changes += 1;
+		if depth == SURFACE {
+			self.growth.plant(&pos, &resource); // Nothing grows back underground
+		}
+		self.events.push(GameEvent::TileChanged {
+			depth,
+			pos,
+			ground,
+			resource,
+		});
+		Ok(())
+	}
+	// Takes what's at `pos`, leaving something to grow back. Returns what was taken and how
+	// much, None if there's nothing to harvest this season.
+	pub fn harvest(&mut self, pos: TilePos) -> Result<Option<(ResourceType, u32)>, Error> {
+		self.load_chunk(
+			self.depth,
+			pos.x.div_euclid(CHUNK_X),
+			pos.y.div_euclid(CHUNK_Y),
+		)?;
+		let (ground, resource) = match self.tile_at(&pos) {
+			Some(tile) => tile.clone(),
+			None => return Ok(None),
+		};
+		let amount = self.season().yield_of(&resource);
+		match resource.harvested() {
+			Some(left) if amount > 0 => {
+				self.set_tile(pos, ground, left)?;
+				self.events.push(GameEvent::Harvested {
+					pos,
+					resource: resource.clone(),
+					amount,
 				});
+				Ok(Some((resource, amount)))
 			}
+			_ => Ok(None),
 		}
-		Ok(())
+	}
+	// Plants grow back and flowers spread, in surface chunks the player has been near
+	pub fn grow(&mut self) -> Vec<Error> {
+		if !self.tick.is_multiple_of(GROWTH_EVERY) {
+			return vec![];
+		}
+		// Nothing on the surface is near a player underground
+		if self.depth == SURFACE {
+			self.growth.visit(self.player.get_position(), self.tick);
+		}
+		let active: Vec<(i64, i64)> = self.growth.active_chunks().collect();
+		let mut errors = vec![];
+		for (chunk_x, chunk_y) in &active {
+			if !self.layers[&SURFACE]
+				.failed_chunks
+				.contains(&(*chunk_x, *chunk_y))
+			{
+				errors.extend(self.load_chunk(SURFACE, *chunk_x, *chunk_y).err());
+			}
+		}
+
+		let (chunks, weather, tick, season) = (
+			&self.layers[&SURFACE].chunks,
+			&self.weather,
+			self.tick,
+			self.season(),
+		);
+		let rate_at = |pos: &TilePos| {
+			weather
+				.at(weather::region_of(pos), tick, season)
+				.growth_rate()
+				* season.growth_rate()
+		};
+		let mut changes: Vec<(TilePos, ResourceType)> = self.growth.grow(
+			|pos| chunks.tile_at(pos).map(|(_, resource)| resource.clone()),
+			rate_at,
+		);
+
+		let rng = fastrand::Rng::with_seed(self.seed ^ self.tick);
+		for (chunk_x, chunk_y) in active {
+			let chunk_pos = TilePos::from((chunk_x, chunk_y)) * &(CHUNK_X, CHUNK_Y).into();
+			let tiles = match chunks.get(chunk_x, chunk_y) {
+				Some(chunk) => &chunk.tiles,
+				None => continue,
+			};
+			for (col, column) in tiles.iter().enumerate() {
+				for (row, (_, resource)) in column.iter().enumerate() {
+					let pos = chunk_pos + &(col as i64, row as i64).into();
+					if resource != &ResourceType::Flower
+						|| rng.f32() >= SPREAD_CHANCE * rate_at(&pos)
+					{
+						continue;
+					}
+					let direction = [(0, 1), (1, 0), (0, -1), (-1, 0)][rng.usize(0..4)];
+					let seeded = pos + &direction.into();
+					// Only into loaded chunks that grow, so what else is loaded doesn't matter
+					if !self.growth.is_active(&seeded) {
+						continue;
+					}
+					if let Some((GroundType::PlainGrass | GroundType::Grass, ResourceType::None)) =
+						chunks.tile_at(&seeded)
+					{
+						if changes.iter().any(|(pos, _)| pos == &seeded) {
+							continue;
+						}
+						changes.push((seeded, ResourceType::Flower));
+					}
+				}
+			}
+		}
+
+		for (pos, resource) in changes {
+			if let Some((ground, _)) = self.layers[&SURFACE].chunks.tile_at(&pos).cloned() {
+				errors.extend(self.set_tile_at(SURFACE, pos, ground, resource).err());
+			}
+		}
+		errors
+	}
+	// Identifies the game state for replays. Which chunks are loaded isn't included, as
+	// generation only depends on the seeds and nothing that changes the world reads a chunk
+	// without loading it first. Stable for a given build only.
+	pub fn state_hash(&self) -> u64 {
+		let mut hasher = DefaultHasher::new();
+		self.ground_seed.hash(&mut hasher);
+		self.resource_seed.hash(&mut hasher);
+		self.tick.hash(&mut hasher);
+		self.player.hash(&mut hasher);
+		self.depth.hash(&mut hasher);
+		// Layers are made when first visited, so those with nothing changed are left out
+		for (depth, layer) in &self.layers {
+			if !layer.edits.is_empty() || !layer.waypoints.is_empty() || !layer.explored.is_empty()
+			{
+				(depth, &layer.edits, &layer.waypoints, &layer.explored).hash(&mut hasher);
+			}
+		}
+		self.growth.growing().hash(&mut hasher);
+		hasher.finish()
+	}
+	// Marks `pos` on the player's layer, or unmarks it if it already was
+	pub fn toggle_waypoint(&mut self, pos: TilePos) {
+		let depth = self.depth;
+		let waypoints = &mut self.layer_mut(depth).waypoints;
+		let placed = waypoints.insert((pos.x, pos.y));
+		if !placed {
+			waypoints.remove(&(pos.x, pos.y));
+		}
+		self.map_changes += 1;
+		self.events
+			.push(GameEvent::WaypointToggled { depth, pos, placed });
+	}
+	// Structures on the surface, and the ladders up from caves beneath their entrances
+	fn points_of_interest(&mut self, depth: Depth, pos: TilePos, size: TilePos) -> Vec<TilePos> {
+		let structures = self.structures_within(pos, size);
+		match depth {
+			SURFACE => structures
+				.iter()
+				.map(|structure| {
+					structure
+						.entrance()
+						.unwrap_or(structure.pos + &(structure.size() / 2))
+				})
+				.collect(),
+			CAVES => structures.iter().filter_map(Structure::entrance).collect(),
+			_ => vec![],
+		}
+	}
+	// What the player's explored of their layer, `size` tiles centred on them
+	pub fn map_around(&mut self, size: TilePos) -> MapImage {
+		let (player, depth) = (*self.player.get_position(), self.depth);
+		let pos = player - &(size / 2);
+		let mut markers: Vec<(TilePos, Marker)> = self
+			.points_of_interest(depth, pos, size)
+			.into_iter()
+			.map(|poi| (poi, Marker::PointOfInterest))
+			.collect();
+		let layer = self.layer_mut(depth);
+		markers.extend(
+			layer
+				.waypoints
+				.iter()
+				.map(|waypoint| (TilePos::from(*waypoint), Marker::Waypoint)),
+		);
+		markers.push((player, Marker::Player));
+		MapImage::render(
+			&layer.chunks,
+			|pos| layer.explored.contains(pos),
+			pos,
+			size,
+			&markers,
+		)
+	}
+	// The last one made if nothing it shows has changed since
+	fn cached_map(
+		&mut self,
+		cache: fn(&mut Self) -> &mut CachedMap,
+		size: TilePos,
+	) -> Arc<MapImage> {
+		let made_for = (
+			self.depth,
+			*self.player.get_position(),
+			size,
+			self.map_changes,
+		);
+		if let Some((cached_for, map)) = cache(self) {
+			if *cached_for == made_for {
+				return map.clone();
+			}
+		}
+		let map = Arc::new(self.map_around(size));
+		*cache(self) = Some((made_for, map.clone()));
+		map
+	}
+	// Generates the whole region first and shows it all, as if explored. Chunks that fail to
+	// generate are left fogged, their errors returned.
+	pub fn export_map(
+		&mut self,
+		depth: Depth,
+		pos: TilePos,
+		size: TilePos,
+	) -> (MapImage, Vec<Error>) {
+		let end = pos + &size - &(1, 1).into();
+		let mut errors = vec![];
+		for chunk_x in pos.x.div_euclid(CHUNK_X)..=end.x.div_euclid(CHUNK_X) {
+			for chunk_y in pos.y.div_euclid(CHUNK_Y)..=end.y.div_euclid(CHUNK_Y) {
+				errors.extend(self.load_chunk(depth, chunk_x, chunk_y).err());
+			}
+		}
+		let markers: Vec<(TilePos, Marker)> = self
+			.points_of_interest(depth, pos, size)
+			.into_iter()
+			.map(|poi| (poi, Marker::PointOfInterest))
+			.collect();
+		let map = MapImage::render(&self.layer_mut(depth).chunks, |_| true, pos, size, &markers);
+		(map, errors)
+	}
+	// Generates the tile's chunk first so what blocks the way doesn't depend on what's been seen.
+	// Tiles that couldn't generate are empty, :. walkable.
+	pub fn can_walk(&mut self, pos: &TilePos) -> bool {
+		let _ = self.load_chunk(
+			self.depth,
+			pos.x.div_euclid(CHUNK_X),
+			pos.y.div_euclid(CHUNK_Y),
+		);
+		self.tile_at(pos)
+			.is_none_or(|(_, resource)| resource.is_walkable())
+	}
+	// Diagonal steps also need both tiles beside them clear, so the player can't slip between two
+	// blockers
+	pub fn can_step(&mut self, direction: TilePos) -> bool {
+		let from = *self.player.get_position();
+		let clear_beside = direction.x == 0
+			|| direction.y == 0
+			|| (self.can_walk(&(from + &(direction.x, 0).into()))
+				&& self.can_walk(&(from + &(0, direction.y).into())));
+		clear_beside && self.can_walk(&(from + &direction))
+	}
+	// What's at `pos` on the player's layer, generating it first as `can_walk` does
+	pub fn inspect(&mut self, pos: &TilePos) -> TileInfo {
+		let (walkable, depth) = (self.can_walk(pos), self.depth);
+		let mut entities = vec![];
+		if self.player.get_position() == pos {
+			entities.push(Entity::Player);
+		}
+		if depth == SURFACE {
+			entities.extend(
+				self.structure_at(pos)
+					.map(|structure| Entity::Structure(structure.kind)),
+			);
+		}
+		if self.layer().waypoints.contains(&(pos.x, pos.y)) {
+			entities.push(Entity::Waypoint);
+		}
+		TileInfo {
+			pos: *pos,
+			depth,
+			explored: self.layer().explored.contains(pos),
+			tile: self.tile_at(pos).cloned(),
+			walkable,
+			entities,
+		}
+	}
+	pub fn move_player(&mut self, dist: TilePos) {
+		let from = *self.player.get_position();
+		self.player.move_by(dist);
+		self.events.push(GameEvent::PlayerMoved {
+			from,
+			to: *self.player.get_position(),
+		});
+		// Stepping onto an entrance or ladder takes the player to its other end
+		let at = *self.player.get_position();
+		let _ = self.load_chunk(
+			self.depth,
+			at.x.div_euclid(CHUNK_X),
+			at.y.div_euclid(CHUNK_Y),
+		);
+		match self.tile_at(&at).map(|(_, resource)| resource) {
+			Some(ResourceType::CaveEntrance) if self.depth == SURFACE => self.enter(CAVES),
+			Some(ResourceType::Ladder) if self.depth == CAVES => self.enter(SURFACE),
+			_ => {}
+		}
+	}
+	fn enter(&mut self, depth: Depth) {
+		let at = *self.player.get_position();
+		let _ = self.load_chunk(depth, at.x.div_euclid(CHUNK_X), at.y.div_euclid(CHUNK_Y));
+		self.depth = depth;
+		self.events.push(GameEvent::DepthChanged { depth });
+	}
+	pub fn stop_player(&mut self) {
+		self.player.stopped_moving();
+		self.events.push(GameEvent::PlayerStopped {
+			at: *self.player.get_position(),
+		});
+	}
+	pub fn drain_events(&mut self) -> Vec<GameEvent> {
+		std::mem::take(&mut self.events)
+	}
+	// What the render thread needs to draw the current view, the full map if it's open and the
+	// tile under the cursor
+	pub fn snapshot(&mut self, map_open: bool, hovered: Option<TilePos>) -> WorldSnapshot {
+		let hovered = hovered.map(|pos| self.inspect(&pos));
+		let minimap = self.cached_map(
+			|world| &mut world.minimap,
+			(MINIMAP_SIZE, MINIMAP_SIZE).into(),
+		);
+		let map = map_open.then(|| {
+			let size = self.view_size * &(MAP_ZOOM, MAP_ZOOM).into();
+			self.cached_map(|world| &mut world.map, size)
+		});
+		let position = self.view_position();
+		let (mut chunks, mut explored) = (Chunks::default(), Explored::default());
+		for (chunk_x, chunk_y) in chunk_range(position, self.view_size) {
+			if let Some(chunk) = self.layer().chunks.get(chunk_x, chunk_y) {
+				chunks.insert(chunk_x, chunk_y, chunk.clone());
+			}
+			explored.insert_chunk(
+				chunk_x,
+				chunk_y,
+				self.layer().explored.chunk(chunk_x, chunk_y),
+			);
+		}
+		WorldSnapshot {
+			tick: self.tick,
+			time: self.time_of_day(),
+			weather: self.weather,
+			player: self.player.clone(),
+			position,
+			size: self.view_size,
+			depth: self.depth,
+			chunks,
+			explored,
+			minimap,
+			map,
+			hovered,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn grass(world: &World, pos: &TilePos) -> bool {
+		matches!(
+			world.tile_at(pos),
+			Some((
+				GroundType::PlainGrass | GroundType::Grass,
+				ResourceType::None
+			))
+		)
+	}
+
+	// Loading more for the view, as playing does, mustn't change how things grow
+	#[test]
+	fn growth_ignores_what_is_loaded() {
+		// The east edge of a chunk with the most grass on both sides
+		let mut probe = World::new("Wet", "Shiny");
+		probe.load((-32, -32).into(), (64, 64).into());
+		let (player, edge) = (-6..6)
+			.flat_map(|chunk_x| (-6..6).map(move |chunk_y| (chunk_x, chunk_y)))
+			.map(|(chunk_x, chunk_y)| {
+				let x = (chunk_x + 1) * CHUNK_X - 1;
+				let edge: Vec<TilePos> = ((chunk_y - 2) * CHUNK_Y..(chunk_y + 3) * CHUNK_Y)
+					.map(|y| (x, y).into())
+					.filter(|pos| grass(&probe, pos) && grass(&probe, &(*pos + &(1, 0).into())))
+					.collect();
+				// Growth covers two chunks either side of the player's
+				(((chunk_x - 2) * CHUNK_X, chunk_y * CHUNK_Y).into(), edge)
+			})
+			.max_by_key(|(_, edge): &(TilePos, Vec<TilePos>)| edge.len())
+			.unwrap();
+		assert!(edge.len() >= 4, "not enough grass");
+
+		let mut worlds = [World::new("Wet", "Shiny"), World::new("Wet", "Shiny")];
+		for world in &mut worlds {
+			world.player.move_to(player);
+			for pos in &edge {
+				world
+					.set_tile(*pos, GroundType::Grass, ResourceType::Flower)
+					.unwrap();
+			}
+		}
+		worlds[0].view_size = (96, 64).into();
+		for tick in (0..5000).step_by(GROWTH_EVERY as usize) {
+			for world in &mut worlds {
+				world.set_tick(tick);
+				assert!(world.grow().is_empty());
+			}
+			worlds[0].load_view();
+		}
+		assert_eq!(worlds[0].state_hash(), worlds[1].state_hash());
+	}
+
+	#[test]
+	fn map_made_again_once_changed() {
+		let mut world = World::new("ground", "resource");
+		world.view_size = (32, 24).into();
+		world.load_view();
+		world.look();
+		let (first, open) = (world.snapshot(false, None), world.snapshot(true, None));
+		let again = world.snapshot(true, None);
+		assert!(Arc::ptr_eq(&first.minimap, &again.minimap));
+		assert!(Arc::ptr_eq(
+			open.map.as_ref().unwrap(),
+			again.map.as_ref().unwrap()
+		));
+		world.toggle_waypoint((2, 2).into());
+		let changed = world.snapshot(true, None);
+		assert!(!Arc::ptr_eq(&again.minimap, &changed.minimap));
+		assert!(!Arc::ptr_eq(
+			again.map.as_ref().unwrap(),
+			changed.map.as_ref().unwrap()
+		));
+		world.look(); // Nothing new seen from the same place
+		assert!(Arc::ptr_eq(
+			&changed.minimap,
+			&world.snapshot(false, None).minimap
+		));
+	}
+
+	#[test]
+	fn inspect_what_is_there() {
+		let mut world = World::new("Wet", "Shiny");
+		let origin = TilePos::from((0, 0));
+		assert!(!world.inspect(&origin).explored);
+		world.look();
+		world.toggle_waypoint(origin);
+		let info = world.inspect(&origin);
+		assert_eq!(
+			(info.pos, info.depth, info.explored),
+			(origin, SURFACE, true)
+		);
+		assert_eq!(info.tile.as_ref(), world.tile_at(&origin));
+		assert_eq!(info.walkable, world.can_walk(&origin));
+		assert_eq!(info.entities, [Entity::Player, Entity::Waypoint]);
+
+		let structure = world
+			.structures_within((-512, -512).into(), (1024, 1024).into())
+			.into_iter()
+			.next()
+			.expect("no structures");
+		let info = world.inspect(&structure.pos);
+		assert!(!info.explored);
+		assert_eq!(info.entities, [Entity::Structure(structure.kind)]);
+		world.enter(CAVES);
+		let info = world.inspect(&structure.pos);
+		assert_eq!(info.depth, CAVES);
+		assert!(info.entities.is_empty()); // Waypoints and structures stay on their layer
+	}
+
+	#[test]
+	fn underground_visits_nothing() {
+		let mut world = World::new("Wet", "Shiny");
+		world.enter(CAVES);
+		world.set_tick(GROWTH_EVERY);
+		assert!(world.grow().is_empty());
+		assert!(world.growth.visited().is_empty());
+		world.enter(SURFACE);
+		world.grow();
+		assert!(!world.growth.visited().is_empty());
+	}
+
+	// Makes `chunk` before and after everything around it, which must come out the same
+	fn assert_load_order_ignored(chunk: (i64, i64)) {
+		let around: Vec<(i64, i64)> = (-2..=2)
+			.flat_map(|x| (-2..=2).map(move |y| (chunk.0 + x, chunk.1 + y)))
+			.collect();
+		let mut first = World::new("Wet", "Shiny");
+		first.load_chunk(SURFACE, chunk.0, chunk.1).unwrap();
+		let mut last = World::new("Wet", "Shiny");
+		for (x, y) in around.iter().rev().filter(|around| **around != chunk) {
+			last.load_chunk(SURFACE, *x, *y).unwrap();
+		}
+		last.load_chunk(SURFACE, chunk.0, chunk.1).unwrap();
+		for (x, y) in &around {
+			first.load_chunk(SURFACE, *x, *y).unwrap();
+			assert!(
+				first.layer().chunks.get(*x, *y).unwrap().tiles
+					== last.layer().chunks.get(*x, *y).unwrap().tiles,
+				"chunk {:?} depends on load order",
+				(x, y)
+			);
+		}
+	}
+
+	fn chunk_of(pos: &TilePos) -> (i64, i64) {
+		(pos.x.div_euclid(CHUNK_X), pos.y.div_euclid(CHUNK_Y))
+	}
+
+	#[test]
+	fn rivers_ignore_load_order() {
+		let mut probe = World::new("Wet", "Shiny");
+		// A chunk that a river flows into from the chunk it starts in
+		let entered = (-8..8)
+			.flat_map(|x| (-8..8).map(move |y| (x, y)))
+			.find_map(|basin| {
+				let river = probe.hydrology.river(basin, &mut probe.heights);
+				let source = chunk_of(river.first()?);
+				river.iter().map(chunk_of).find(|chunk| *chunk != source)
+			})
+			.expect("no river leaves its chunk");
+		assert_load_order_ignored(entered);
+	}
+
+	#[test]
+	fn structures_ignore_load_order() {
+		let mut probe = World::new("Wet", "Shiny");
+		// A chunk that a structure reaches into from the chunk its corner is in
+		let entered = probe
+			.structures_within((-512, -512).into(), (1024, 1024).into())
+			.into_iter()
+			.find_map(|structure| {
+				let far = structure.pos + &structure.size() - &(1, 1).into();
+				Some(chunk_of(&far)).filter(|chunk| *chunk != chunk_of(&structure.pos))
+			})
+			.expect("no structure crosses a chunk edge");
+		assert_load_order_ignored(entered);
 	}
 }
@@ -5,7 +5,10 @@ use crow::glutin::dpi::{
 	PhysicalSize,
 };
 
-use crate::TILE_SIZE;
+use crate::{
+	Error,
+	TILE_SIZE,
+};
 
 use super::tile_pos::TilePos;
 
@@ -34,14 +37,18 @@ impl From<PhysicalSize<u32>> for PixelPos {
 		}
 	}
 }
-impl Into<(i64, i64)> for PixelPos {
-	fn into(self) -> (i64, i64) {
-		(self.x, self.y)
+impl From<PixelPos> for (i64, i64) {
+	fn from(from: PixelPos) -> Self {
+		(from.x, from.y)
 	}
 }
-impl Into<(i32, i32)> for PixelPos {
-	fn into(self) -> (i32, i32) {
-		(self.x.try_into().unwrap(), self.y.try_into().unwrap()) // TODO: Handle
+impl TryFrom<PixelPos> for (i32, i32) {
+	type Error = Error;
+	fn try_from(from: PixelPos) -> Result<Self, Self::Error> {
+		match (from.x.try_into(), from.y.try_into()) {
+			(Ok(x), Ok(y)) => Ok((x, y)),
+			_ => Err(Error::CoordinateOverflow(from)),
+		}
 	}
 }
 impl Add<&PixelPos> for PixelPos {
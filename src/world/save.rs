@@ -0,0 +1,342 @@
+use {
+	super::{
+		clock::Clock,
+		growth::Growth,
+		layer::Depth,
+		weather,
+		World,
+	},
+	crate::{
+		assets::Player,
+		tile::{
+			GroundType,
+			ResourceType,
+		},
+	},
+	serde::{
+		de::DeserializeOwned,
+		Deserialize,
+		Serialize,
+	},
+	std::{
+		collections::BTreeMap,
+		fmt,
+		fs,
+		path::{
+			Path,
+			PathBuf,
+		},
+	},
+};
+
+pub const AUTOSAVE_EVERY: u64 = 600; // Ticks, :. once a minute
+const WORLD_FILE: &str = "world.toml";
+
+#[derive(Debug)]
+pub enum SaveError {
+	Read { path: PathBuf, error: String },
+	Parse { path: PathBuf, error: String },
+	Write { path: PathBuf, error: String },
+}
+impl fmt::Display for SaveError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use SaveError::*;
+		match self {
+			Read { path, error } => write!(f, "could not read save {:?}: {}", path, error),
+			Parse { path, error } => write!(f, "could not parse save {:?}: {}", path, error),
+			Write { path, error } => write!(f, "could not write save {:?}: {}", path, error),
+		}
+	}
+}
+
+// What isn't part of any one layer
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct WorldSave {
+	ground_seed: String,
+	resource_seed: String,
+	ticks: u64, // Run so far, the next tick run is this one
+	depth: Depth,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	layers: Vec<Depth>, // Each in its own file
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	growing: Vec<(i64, i64, u64)>, // Tile, growth so far
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	visited: Vec<(i64, i64, u64)>, // Chunk, last tick near the player
+	clock: Clock,
+	player: Player,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Edit {
+	x: i64,
+	y: i64,
+	ground: GroundType,
+	resource: ResourceType,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ExploredChunk {
+	chunk: (i64, i64),
+	tiles: String, // Hex, as TOML integers are signed
+}
+
+// Only what the player changed, the rest is generated again from the seeds
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct LayerSave {
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	waypoints: Vec<(i64, i64)>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	explored: Vec<ExploredChunk>,
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	edits: Vec<Edit>,
+}
+
+// A directory with the world in world.toml and each layer in layer-<depth>.toml. Plain data, so it
+// can be read on one thread and made into a world on another.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Save {
+	world: WorldSave,
+	layers: BTreeMap<Depth, LayerSave>,
+}
+impl Save {
+	// `ticks` is how many have been run, as the world only knows the last
+	pub fn of(world: &World, ticks: u64) -> Self {
+		// Layers with nothing changed are made again when visited, as for `World::state_hash`
+		let layers: BTreeMap<Depth, LayerSave> = world
+			.layers
+			.iter()
+			.filter(|(_, layer)| {
+				!layer.edits.is_empty() || !layer.waypoints.is_empty() || !layer.explored.is_empty()
+			})
+			.map(|(depth, layer)| {
+				let layer = LayerSave {
+					waypoints: layer.waypoints.iter().copied().collect(),
+					explored: layer
+						.explored
+						.chunks()
+						.map(|(chunk, tiles)| ExploredChunk {
+							chunk,
+							tiles: format!("{:016x}", tiles),
+						})
+						.collect(),
+					edits: layer
+						.edits
+						.iter()
+						.map(|((x, y), (ground, resource))| Edit {
+							x: *x,
+							y: *y,
+							ground: ground.clone(),
+							resource: resource.clone(),
+						})
+						.collect(),
+				};
+				(*depth, layer)
+			})
+			.collect();
+		let flatten = |map: &BTreeMap<(i64, i64), u64>| {
+			map.iter().map(|((x, y), value)| (*x, *y, *value)).collect()
+		};
+		Self {
+			world: WorldSave {
+				ground_seed: world.ground_seed.clone(),
+				resource_seed: world.resource_seed.clone(),
+				ticks,
+				depth: world.depth,
+				layers: layers.keys().copied().collect(),
+				growing: flatten(world.growth.growing()),
+				visited: flatten(world.growth.visited()),
+				clock: world.clock,
+				player: world.player.clone(),
+			},
+			layers,
+		}
+	}
+	pub fn exists(dir: impl AsRef<Path>) -> bool {
+		dir.as_ref().join(WORLD_FILE).is_file()
+	}
+	pub fn load(dir: impl AsRef<Path>) -> Result<Self, SaveError> {
+		let dir = dir.as_ref();
+		let world: WorldSave = read(&dir.join(WORLD_FILE))?;
+		let layers = world
+			.layers
+			.iter()
+			.map(|depth| Ok((*depth, read(&dir.join(layer_file(*depth)))?)))
+			.collect::<Result<_, SaveError>>()?;
+		Ok(Self { world, layers })
+	}
+	// Layers are written before the world, so an interrupted save still loads the last one whole
+	// unless it added a layer
+	pub fn write(&self, dir: impl AsRef<Path>) -> Result<(), SaveError> {
+		let dir = dir.as_ref();
+		fs::create_dir_all(dir).map_err(|error| SaveError::Write {
+			path: dir.to_path_buf(),
+			error: error.to_string(),
+		})?;
+		for (depth, layer) in &self.layers {
+			write(&dir.join(layer_file(*depth)), layer)?;
+		}
+		write(&dir.join(WORLD_FILE), &self.world)
+	}
+	pub fn ticks(&self) -> u64 {
+		self.world.ticks
+	}
+	pub fn into_world(self) -> World {
+		let Save {
+			world: saved,
+			layers,
+		} = self;
+		let mut world = World::new(&saved.ground_seed, &saved.resource_seed);
+		world.tick = saved.ticks.saturating_sub(1); // Of the last tick run
+		world.clock = saved.clock;
+		world.player = saved.player;
+		let unflatten = |list: Vec<(i64, i64, u64)>| {
+			list.into_iter()
+				.map(|(x, y, value)| ((x, y), value))
+				.collect()
+		};
+		world.growth = Growth::restore(unflatten(saved.growing), unflatten(saved.visited));
+		world.local_weather = world.weather.at(
+			weather::region_of(world.player.get_position()),
+			world.tick,
+			world.season(),
+		);
+		for (depth, saved) in layers {
+			let layer = world.layer_mut(depth);
+			layer.waypoints.extend(saved.waypoints);
+			for ExploredChunk { chunk, tiles } in saved.explored {
+				// Unreadable masks are left unexplored rather than losing the whole save
+				let tiles = u64::from_str_radix(&tiles, 16).unwrap_or(0);
+				layer.explored.insert_chunk(chunk.0, chunk.1, tiles);
+			}
+			for Edit {
+				x,
+				y,
+				ground,
+				resource,
+			} in saved.edits
+			{
+				layer.edits.insert((x, y), (ground, resource));
+			}
+		}
+		world.depth = saved.depth;
+		world.layer_mut(saved.depth); // Caves are only made on entering them
+		world
+	}
+}
+
+fn layer_file(depth: Depth) -> String {
+	format!("layer-{}.toml", depth)
+}
+
+fn read<T: DeserializeOwned>(path: &Path) -> Result<T, SaveError> {
+	let source = fs::read_to_string(path).map_err(|error| SaveError::Read {
+		path: path.to_path_buf(),
+		error: error.to_string(),
+	})?;
+	toml::from_str(&source).map_err(|error| SaveError::Parse {
+		path: path.to_path_buf(),
+		error: error.to_string(),
+	})
+}
+
+fn write(path: &Path, value: &impl Serialize) -> Result<(), SaveError> {
+	let write_error = |error: String| SaveError::Write {
+		path: path.to_path_buf(),
+		error,
+	};
+	let source = toml::to_string(value).map_err(|error| write_error(error.to_string()))?;
+	fs::write(path, source).map_err(|error| write_error(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+	use {
+		super::*,
+		crate::world::{
+			layer::{
+				CAVES,
+				SURFACE,
+			},
+			tile::TilePos,
+		},
+	};
+
+	fn dir(name: &str) -> PathBuf {
+		std::env::temp_dir().join(format!("ky_space_{}_{}", std::process::id(), name))
+	}
+
+	fn played() -> World {
+		let mut world = World::new("ground", "resource");
+		world.view_size = (32, 24).into();
+		world.load_view();
+		world.look();
+		world
+			.set_tile(
+				TilePos::from((3, 4)),
+				GroundType::Bricks,
+				ResourceType::Flower,
+			)
+			.unwrap();
+		world.toggle_waypoint((5, -2).into());
+		world.set_tick(1234);
+		world
+	}
+
+	#[test]
+	fn round_trip() {
+		let (world, dir) = (played(), dir("round_trip"));
+		Save::of(&world, world.tick + 1).write(&dir).unwrap();
+		let mut loaded = Save::load(&dir).unwrap().into_world();
+		fs::remove_dir_all(&dir).unwrap();
+		assert_eq!(loaded.state_hash(), world.state_hash());
+		loaded.view_size = world.view_size; // Sent by the window, not saved
+		loaded.load_view();
+		assert_eq!(
+			loaded.tile_at(&(3, 4).into()),
+			Some(&(GroundType::Bricks, ResourceType::Flower))
+		);
+	}
+
+	#[test]
+	fn layer_per_file() {
+		let (mut world, dir) = (played(), dir("layer_per_file"));
+		world.enter(CAVES);
+		world
+			.set_tile((1, 1).into(), GroundType::Dirt, ResourceType::None)
+			.unwrap();
+		let save = Save::of(&world, world.tick + 1);
+		save.write(&dir).unwrap();
+		assert!(Save::exists(&dir));
+		assert!(dir.join(layer_file(CAVES)).is_file());
+		let loaded = Save::load(&dir).unwrap();
+		fs::remove_dir_all(&dir).unwrap();
+		assert_eq!(loaded, save);
+		assert_eq!(loaded.into_world().state_hash(), world.state_hash());
+	}
+
+	#[test]
+	fn explored_kept() {
+		let (mut world, dir) = (played(), dir("explored_kept"));
+		world.enter(CAVES);
+		world.look();
+		Save::of(&world, world.tick + 1).write(&dir).unwrap();
+		let loaded = Save::load(&dir).unwrap().into_world();
+		fs::remove_dir_all(&dir).unwrap();
+		let explored =
+			|world: &World, depth| world.layers[&depth].explored.chunks().collect::<Vec<_>>();
+		for depth in [SURFACE, CAVES] {
+			assert!(!explored(&world, depth).is_empty());
+			assert_eq!(explored(&loaded, depth), explored(&world, depth));
+		}
+	}
+
+	#[test]
+	fn missing_layer() {
+		let (world, dir) = (played(), dir("missing_layer"));
+		Save::of(&world, world.tick + 1).write(&dir).unwrap();
+		fs::remove_file(dir.join(layer_file(SURFACE))).unwrap();
+		let loaded = Save::load(&dir);
+		fs::remove_dir_all(&dir).unwrap();
+		assert!(matches!(loaded, Err(SaveError::Read { .. })));
+	}
+}
@@ -0,0 +1,127 @@
+use {
+	crate::{
+		atlas::TextureType,
+		frame_manager::draw::Draw,
+		tile::{
+			GroundType,
+			ResourceType,
+		},
+	},
+	serde::{
+		Deserialize,
+		Serialize,
+	},
+	std::{
+		fmt,
+		str::FromStr,
+	},
+};
+
+pub const DEFAULT_SEASON_DAYS: u64 = 3;
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Season {
+	Spring,
+	Summer,
+	Autumn,
+	Winter,
+}
+impl Season {
+	pub const ALL: [Season; 4] = [
+		Season::Spring,
+		Season::Summer,
+		Season::Autumn,
+		Season::Winter,
+	];
+	pub fn of_day(day: u64, season_days: u64) -> Self {
+		Self::ALL[(day / season_days.max(1) % 4) as usize]
+	}
+	// Multiplies how fast plants grow back, alongside the weather
+	pub fn growth_rate(&self) -> f32 {
+		match self {
+			Season::Spring => 1.5,
+			Season::Summer => 1.0,
+			Season::Autumn => 0.5,
+			Season::Winter => 0.0,
+		}
+	}
+	// How much harvesting `resource` gives, 0 if there's nothing to take this time of year
+	pub fn yield_of(&self, resource: &ResourceType) -> u32 {
+		use ResourceType::*;
+		match (resource, self) {
+			(Tree | Conifer, _) => 3,
+			(YoungTree | Sapling, _) => 1,
+			(IronOre | CopperOre, _) => 2,
+			(SilverOre, _) => 1,
+			(Firewood, _) => 3,
+			(Log, _) => 2,
+			(Cactus, _) => 1,
+			(JunglePlant, Season::Winter) => 1,
+			(JunglePlant, _) => 2,
+			(Mushroom, Season::Autumn) => 3,
+			(Mushroom, Season::Winter) => 0,
+			(Mushroom, _) => 1,
+			(Reeds, Season::Winter) => 0,
+			(Reeds, _) => 2,
+			(Bush, Season::Summer) => 3,
+			(Bush, Season::Autumn) => 2,
+			(Bush, Season::Spring) => 1,
+			(Bush, Season::Winter) => 0,
+			(Flower, Season::Winter) => 0,
+			(Flower, _) => 1,
+			(Rock | None | Stump | BareBush | CaveEntrance | Ladder, _) => 0,
+		}
+	}
+	// Chance out of 20 of each weather for a spell: clear, rain, snow, fog
+	pub fn weather_odds(&self) -> [u32; 4] {
+		match self {
+			Season::Spring => [9, 7, 1, 3],
+			Season::Summer => [13, 5, 0, 2],
+			Season::Autumn => [8, 6, 2, 4],
+			Season::Winter => [7, 1, 9, 3],
+		}
+	}
+}
+impl fmt::Display for Season {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{:?}", self)
+	}
+}
+impl FromStr for Season {
+	type Err = String;
+	fn from_str(name: &str) -> Result<Self, Self::Err> {
+		Self::ALL
+			.iter()
+			.find(|season| season.to_string().eq_ignore_ascii_case(name))
+			.copied()
+			.ok_or_else(|| format!("unknown season {:?}", name))
+	}
+}
+
+// Seasonal sprite for a tile, drawn instead of the usual one where the atlas has it
+#[derive(Clone, Debug, PartialEq)]
+pub enum InSeason {
+	Ground(GroundType, Season),
+	Resource(ResourceType, Season),
+}
+impl Draw for InSeason {
+	fn texture_type(&self) -> Option<TextureType> {
+		Some(match self {
+			InSeason::Ground(ground, season) => TextureType::SeasonalGround {
+				ground: ground.clone(),
+				season: *season,
+			},
+			InSeason::Resource(resource, season) => TextureType::SeasonalResource {
+				resource: resource.clone(),
+				season: *season,
+			},
+		})
+	}
+	fn scale(&self) -> u32 {
+		match self {
+			// From the tree sheets, which have double sized cells
+			InSeason::Resource(ResourceType::Tree | ResourceType::YoungTree, _) => 2,
+			_ => 4,
+		}
+	}
+}
@@ -0,0 +1,222 @@
+use {
+	super::{
+		autotile::Transition,
+		cave,
+		chunk_range,
+		clock::TimeOfDay,
+		inspect::TileInfo,
+		layer::{
+			Depth,
+			SURFACE,
+		},
+		map::{
+			MapImage,
+			MapTexture,
+			MINIMAP_MARGIN,
+			MINIMAP_SCALE,
+		},
+		season::InSeason,
+		tile::{
+			PixelPos,
+			TilePos,
+		},
+		vision::{
+			self,
+			Explored,
+		},
+		weather::{
+			self,
+			SnowOverlay,
+			Weather,
+			WeatherMap,
+		},
+		Chunks,
+	},
+	crate::{
+		animation::phase_offset,
+		assets::Player,
+		atlas::Atlas,
+		frame_manager::draw::Draw,
+		Error,
+		CHUNK_X,
+		CHUNK_Y,
+		TILE_SIZE,
+	},
+	crow::{
+		Context,
+		WindowSurface,
+	},
+	std::sync::Arc,
+};
+
+const HIGHLIGHT: (f32, f32, f32, f32) = (1.0, 1.0, 0.6, 1.0); // Around the tile under the cursor
+
+// Immutable copy of the visible world, published by the tick thread after it changes
+#[derive(Clone)]
+pub struct WorldSnapshot {
+	pub tick: u64,
+	pub time: TimeOfDay,
+	pub weather: WeatherMap,
+	pub player: Player,
+	pub position: TilePos, // Of the board
+	pub size: TilePos,
+	pub depth: Depth, // Only this layer is drawn
+	pub(super) chunks: Chunks,
+	pub(super) explored: Explored, // Of the board's chunks
+	pub minimap: Arc<MapImage>,
+	pub map: Option<Arc<MapImage>>, // Full screen, while open
+	pub hovered: Option<TileInfo>,  // Under the cursor
+}
+impl WorldSnapshot {
+	// Anything that fails to draw is skipped, the errors are returned. Weather is drawn last,
+	// over everything else.
+	pub fn draw(
+		&self,
+		ctx: &mut Context,
+		surface: &mut WindowSurface,
+		atlas: &Atlas,
+		offset: PixelPos,
+		frame: usize,
+	) -> Vec<Error> {
+		let pos = self.position;
+
+		// Caves have no sky, so no daylight, weather or seasons
+		let outdoors = self.depth == SURFACE;
+		let (light, season) = (
+			if outdoors {
+				self.time.tint()
+			} else {
+				cave::LIGHT
+			},
+			self.time.season,
+		);
+		let mut errors = vec![];
+		let mut particles = vec![];
+		for (chunk_x, chunk_y) in chunk_range(pos, self.size) {
+			let chunk = match self.chunks.get(chunk_x, chunk_y) {
+				Some(chunk) => chunk,
+				None => continue,
+			};
+			let chunk_pos = TilePos::from((chunk_x, chunk_y)) * &(CHUNK_X, CHUNK_Y).into();
+			for (col, tiles) in chunk.tiles.iter().enumerate() {
+				for (row, (ground, resource)) in tiles.iter().enumerate() {
+					let tile_pos = chunk_pos + &(col as i64, row as i64).into(); // Both within a chunk
+					let rel_pos = tile_pos - &pos;
+					let pixel_pos = PixelPos::from(rel_pos) + &offset;
+					let tile_frame = frame.wrapping_add(phase_offset(&tile_pos));
+					let region = weather::region_of(&tile_pos);
+					let tile_weather = if outdoors {
+						self.weather.at(region, self.tick, season)
+					} else {
+						Weather::Clear
+					};
+					let tint = if self.explored.contains(&tile_pos) {
+						tile_weather.tint(light)
+					} else {
+						vision::unexplored(tile_weather.tint(light))
+					};
+					particles.push((tile_weather, pixel_pos, tile_pos));
+					let transitions: Vec<Transition> = self
+						.chunks
+						.transitions_at(&tile_pos)
+						.into_iter()
+						.filter(|transition| atlas.contains(transition))
+						// Edges are only drawn in their usual colours, so not over seasonal ground
+						.filter(|transition| {
+							!outdoors
+								|| !atlas
+									.contains(&InSeason::Ground(transition.ground.clone(), season))
+						})
+						.collect();
+					let seasonal_ground = InSeason::Ground(ground.clone(), season);
+					let seasonal_resource = InSeason::Resource(resource.clone(), season);
+					let ground_layer: &dyn Draw = if outdoors && atlas.contains(&seasonal_ground) {
+						&seasonal_ground
+					} else {
+						ground
+					};
+					let resource_layer: &dyn Draw =
+						if outdoors && atlas.contains(&seasonal_resource) {
+							&seasonal_resource
+						} else {
+							resource
+						};
+					let snowy = outdoors && self.weather.snow_covered(region, self.tick, season);
+					let snow_ground = SnowOverlay::Ground(ground.clone());
+					let snow_resource = SnowOverlay::Resource(resource.clone());
+					let snow = |overlay| {
+						(snowy && atlas.contains(overlay)).then_some(overlay as &dyn Draw)
+					};
+					let layers = std::iter::once(ground_layer)
+						.chain(transitions.iter().map(|transition| transition as &dyn Draw))
+						.chain(snow(&snow_ground))
+						.chain(std::iter::once(resource_layer))
+						.chain(snow(&snow_resource));
+					for layer in layers {
+						if let Err(error) =
+							layer.draw(ctx, surface, pixel_pos, atlas, tile_frame, tint)
+						{
+							errors.push(error);
+						}
+					}
+					if &tile_pos == self.player.get_position() {
+						// TODO: Do this more generically
+						if let Err(error) = self
+							.player
+							.draw(ctx, surface, pixel_pos, atlas, frame, tint)
+						{
+							errors.push(error);
+						}
+					}
+				}
+			}
+		}
+		for (tile_weather, pixel_pos, tile_pos) in particles {
+			match pixel_pos.try_into() {
+				Ok(pos) => tile_weather.draw_particles(ctx, surface, pos, &tile_pos, frame),
+				Err(error) => errors.push(error),
+			}
+		}
+		if let Some(hovered) = &self.hovered {
+			// Outlined over the neighbours drawn after it
+			let lower_left = PixelPos::from(hovered.pos - &pos) + &offset;
+			let upper_right = lower_left + &(TILE_SIZE - 1, TILE_SIZE - 1).into();
+			match (lower_left.try_into(), upper_right.try_into()) {
+				(Ok(lower_left), Ok(upper_right)) => {
+					ctx.debug_rectangle(surface, lower_left, upper_right, HIGHLIGHT)
+				}
+				(Err(error), _) | (_, Err(error)) => errors.push(error),
+			}
+		}
+		errors
+	}
+	// Over the board, the full map as large as fits or otherwise the minimap in the top right
+	pub fn draw_map(
+		&self,
+		ctx: &mut Context,
+		surface: &mut WindowSurface,
+		texture: &mut MapTexture,
+		window_size: PixelPos,
+	) -> Result<(), Error> {
+		match &self.map {
+			Some(map) => {
+				ctx.clear_color(surface, (0.0, 0.0, 0.0, 1.0));
+				let (width, height) = map.image.dimensions();
+				let scale = (window_size.x / i64::from(width.max(1)))
+					.min(window_size.y / i64::from(height.max(1)))
+					.max(1);
+				let size = PixelPos::from((i64::from(width) * scale, i64::from(height) * scale));
+				texture.draw(ctx, surface, map, (window_size - &size) / 2, scale as u32)
+			}
+			None => {
+				let (width, height) = self.minimap.image.dimensions();
+				let size = PixelPos::from((
+					i64::from(width * MINIMAP_SCALE),
+					i64::from(height * MINIMAP_SCALE),
+				));
+				let pos = window_size - &size - &(MINIMAP_MARGIN, MINIMAP_MARGIN).into();
+				texture.draw(ctx, surface, &self.minimap, pos, MINIMAP_SCALE)
+			}
+		}
+	}
+}
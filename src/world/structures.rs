@@ -0,0 +1,237 @@
+use {
+	super::{
+		biome::SEA_LEVEL,
+		heights::Heights,
+		tile::TilePos,
+	},
+	crate::{
+		tile::{
+			GroundType,
+			ResourceType,
+		},
+		CHUNK_X,
+		CHUNK_Y,
+	},
+	std::{
+		collections::{
+			hash_map::DefaultHasher,
+			HashMap,
+		},
+		hash::{
+			Hash,
+			Hasher,
+		},
+	},
+};
+
+pub const SITE_CHUNKS: i64 = 8; // At most one structure in each site, :. 32x32 tiles
+const SITE_CHANCE: u32 = 3; // 1 in
+const SITE_MARGIN: i64 = 4; // Tiles kept clear at the edge of a site, :. prefabs up to 8 wide
+const HILLS: f64 = 0.4; // Of the ground map, caves are dug in above this
+const SHORE: f64 = SEA_LEVEL + 0.1; // Nothing is built below this
+const CLEARING: i64 = 2; // Radius of open cave around the ladder under an entrance
+
+// Prefab rows run north to south. ' ' leaves the generated tile, anything else is in `stamp`.
+const RUINS: [&str; 7] = [
+	"##.#  #", "#.....#", "  . ,..", "#.,,,.#", "#...,. ", "#.....#", "###. ##",
+];
+const CAMP: [&str; 5] = [" ::: ", ":l:l:", "::f::", ":l:l:", " ::: "];
+const CAVE_ENTRANCE: [&str; 3] = ["rrr", "ror", "sss"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StructureKind {
+	Ruins,
+	Camp,
+	CaveEntrance,
+}
+impl StructureKind {
+	fn prefab(&self) -> &'static [&'static str] {
+		match self {
+			StructureKind::Ruins => &RUINS,
+			StructureKind::Camp => &CAMP,
+			StructureKind::CaveEntrance => &CAVE_ENTRANCE,
+		}
+	}
+	pub fn size(&self) -> TilePos {
+		let prefab = self.prefab();
+		(prefab[0].len() as i64, prefab.len() as i64).into()
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Structure {
+	pub kind: StructureKind,
+	pub pos: TilePos, // South west corner
+}
+impl Structure {
+	pub fn size(&self) -> TilePos {
+		self.kind.size()
+	}
+	pub fn contains(&self, pos: &TilePos) -> bool {
+		let offset = *pos - &self.pos;
+		let size = self.size();
+		offset.x >= 0 && offset.y >= 0 && offset.x < size.x && offset.y < size.y
+	}
+	// Where a cave entrance leads down, None for other structures
+	pub fn entrance(&self) -> Option<TilePos> {
+		let prefab = self.kind.prefab();
+		prefab.iter().enumerate().find_map(|(row, line)| {
+			line.find('o')
+				.map(|x| self.pos + &(x as i64, (prefab.len() - 1 - row) as i64).into())
+		})
+	}
+	// What the prefab puts at `pos`, None where it leaves the generated tile
+	fn tile_at(&self, pos: &TilePos) -> Option<(GroundType, ResourceType)> {
+		if !self.contains(pos) {
+			return None;
+		}
+		let offset = *pos - &self.pos;
+		let prefab = self.kind.prefab();
+		let row = prefab[prefab.len() - 1 - offset.y as usize];
+		stamp(row.as_bytes()[offset.x as usize] as char)
+	}
+}
+
+fn stamp(key: char) -> Option<(GroundType, ResourceType)> {
+	use {
+		GroundType::*,
+		ResourceType::*,
+	};
+	Some(match key {
+		'.' => (Bricks, None),
+		',' => (Grass, None),
+		'#' => (Bricks, Rock),
+		':' => (Dirt, None),
+		'f' => (Dirt, Firewood),
+		'l' => (Dirt, Log),
+		's' => (Stone, None),
+		'r' => (Stone, Rock),
+		'o' => (Stone, CaveEntrance),
+		_ => return Option::None,
+	})
+}
+
+// Each site may hold a structure, picked by the seed and the ground there. Sites are whole
+// chunks and structures never cross their edges, so stamping a chunk only needs its own site.
+pub struct Structures {
+	seed: u64,
+	sites: HashMap<(i64, i64), Option<Structure>>,
+}
+impl Structures {
+	pub fn new(seed: u64) -> Self {
+		Self {
+			seed,
+			sites: HashMap::new(),
+		}
+	}
+	pub fn stamp(
+		&mut self,
+		chunk: (i64, i64),
+		tiles: &mut [Vec<(GroundType, ResourceType)>],
+		heights: &mut Heights,
+	) {
+		let site = (
+			chunk.0.div_euclid(SITE_CHUNKS),
+			chunk.1.div_euclid(SITE_CHUNKS),
+		);
+		let structure = match self.in_site(site, heights) {
+			Some(structure) => structure,
+			None => return,
+		};
+		let origin = TilePos::from((chunk.0 * CHUNK_X, chunk.1 * CHUNK_Y));
+		for (x, column) in tiles.iter_mut().enumerate() {
+			for (y, tile) in column.iter_mut().enumerate() {
+				if let Some(stamped) = structure.tile_at(&(origin + &(x as i64, y as i64).into())) {
+					*tile = stamped;
+				}
+			}
+		}
+	}
+	// Under each cave entrance, a ladder back up in a clearing
+	pub fn stamp_exits(
+		&mut self,
+		chunk: (i64, i64),
+		tiles: &mut [Vec<(GroundType, ResourceType)>],
+		heights: &mut Heights,
+	) {
+		let origin = TilePos::from((chunk.0 * CHUNK_X, chunk.1 * CHUNK_Y));
+		let exits: Vec<TilePos> = self
+			.within(
+				origin - &(CLEARING, CLEARING).into(),
+				(CHUNK_X + CLEARING * 2, CHUNK_Y + CLEARING * 2).into(),
+				heights,
+			)
+			.iter()
+			.filter_map(Structure::entrance)
+			.collect();
+		for (x, column) in tiles.iter_mut().enumerate() {
+			for (y, tile) in column.iter_mut().enumerate() {
+				let pos = origin + &(x as i64, y as i64).into();
+				for exit in &exits {
+					let offset = pos - exit;
+					if offset == (0, 0).into() {
+						*tile = (GroundType::Stone, ResourceType::Ladder);
+					} else if offset.x * offset.x + offset.y * offset.y <= CLEARING * CLEARING {
+						*tile = (GroundType::Stone, ResourceType::None);
+					}
+				}
+			}
+		}
+	}
+	// Structures overlapping the board at `pos` of `size` tiles
+	pub fn within(&mut self, pos: TilePos, size: TilePos, heights: &mut Heights) -> Vec<Structure> {
+		let (site_x, site_y) = (SITE_CHUNKS * CHUNK_X, SITE_CHUNKS * CHUNK_Y);
+		let mut found = vec![];
+		for x in pos.x.div_euclid(site_x)..=(pos.x + size.x).div_euclid(site_x) {
+			for y in pos.y.div_euclid(site_y)..=(pos.y + size.y).div_euclid(site_y) {
+				found.extend(self.in_site((x, y), heights).filter(|structure| {
+					let end = structure.pos + &structure.size();
+					structure.pos.x < pos.x + size.x
+						&& structure.pos.y < pos.y + size.y
+						&& end.x > pos.x && end.y > pos.y
+				}));
+			}
+		}
+		found
+	}
+	pub fn at(&mut self, pos: &TilePos, heights: &mut Heights) -> Option<Structure> {
+		self.within(*pos, (1, 1).into(), heights)
+			.into_iter()
+			.find(|structure| structure.contains(pos))
+	}
+	fn in_site(&mut self, site: (i64, i64), heights: &mut Heights) -> Option<Structure> {
+		if let Some(structure) = self.sites.get(&site) {
+			return *structure;
+		}
+		let structure = self.place(site, heights);
+		self.sites.insert(site, structure);
+		structure
+	}
+	fn place(&self, site: (i64, i64), heights: &mut Heights) -> Option<Structure> {
+		let mut hasher = DefaultHasher::new();
+		(self.seed, "structures", site).hash(&mut hasher);
+		let rng = fastrand::Rng::with_seed(hasher.finish());
+		if rng.u32(0..SITE_CHANCE) != 0 {
+			return None;
+		}
+		let (size_x, size_y) = (SITE_CHUNKS * CHUNK_X, SITE_CHUNKS * CHUNK_Y);
+		let centre = TilePos::from((
+			site.0 * size_x + rng.i64(SITE_MARGIN..size_x - SITE_MARGIN),
+			site.1 * size_y + rng.i64(SITE_MARGIN..size_y - SITE_MARGIN),
+		));
+		let height = heights.at(&centre);
+		let kind = if height < SHORE {
+			return None;
+		} else if height > HILLS {
+			StructureKind::CaveEntrance
+		} else if rng.bool() {
+			StructureKind::Ruins
+		} else {
+			StructureKind::Camp
+		};
+		Some(Structure {
+			kind,
+			pos: centre - &(kind.size() / 2),
+		})
+	}
+}
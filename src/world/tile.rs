@@ -20,6 +20,7 @@ impl From<TilePos> for Direction {
 	}
 }
 
+#[allow(clippy::wrong_self_convention)]
 pub trait Asi64 {
 	fn as_i64(self) -> i64;
 }
@@ -32,6 +33,7 @@ impl Asi64 for Ordering {
 		}
 	}
 }
+#[allow(clippy::wrong_self_convention)]
 pub trait AsOrdering {
 	fn as_ordering(self) -> Ordering;
 }
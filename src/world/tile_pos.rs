@@ -6,10 +6,14 @@ use {
 	},
 	crate::TILE_SIZE,
 	crow::glutin::dpi::PhysicalSize,
+	serde::{
+		Deserialize,
+		Serialize,
+	},
 	std::ops::*,
 };
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TilePos {
 	pub x: i64,
 	pub y: i64,
@@ -0,0 +1,137 @@
+use {
+	super::tile::TilePos,
+	crate::{
+		CHUNK_X,
+		CHUNK_Y,
+	},
+	std::collections::BTreeMap,
+};
+
+pub const VISION_RADIUS: i64 = 8; // Tiles
+const UNEXPLORED_LIGHT: f32 = 0.3; // Of the usual light, tiles not yet seen are drawn this dark
+
+const _: () = assert!(
+	CHUNK_X * CHUNK_Y <= 64,
+	"a chunk's tiles must fit in a u64 mask"
+);
+
+// A bit per tile of each chunk, set once the player has seen it
+#[derive(Clone, Debug, Default, Hash)]
+pub struct Explored(BTreeMap<(i64, i64), u64>);
+impl Explored {
+	fn bit_of(pos: &TilePos) -> ((i64, i64), u64) {
+		let (x, y) = (pos.x.rem_euclid(CHUNK_X), pos.y.rem_euclid(CHUNK_Y));
+		(
+			(pos.x.div_euclid(CHUNK_X), pos.y.div_euclid(CHUNK_Y)),
+			1 << (x * CHUNK_Y + y),
+		)
+	}
+	pub fn contains(&self, pos: &TilePos) -> bool {
+		let (chunk, bit) = Self::bit_of(pos);
+		self.chunk(chunk.0, chunk.1) & bit != 0
+	}
+	// Whether it wasn't already
+	pub fn insert(&mut self, pos: &TilePos) -> bool {
+		let (chunk, bit) = Self::bit_of(pos);
+		let mask = self.0.entry(chunk).or_default();
+		let new = *mask & bit == 0;
+		*mask |= bit;
+		new
+	}
+	pub fn is_empty(&self) -> bool {
+		self.0.is_empty()
+	}
+	pub fn chunk(&self, chunk_x: i64, chunk_y: i64) -> u64 {
+		self.0.get(&(chunk_x, chunk_y)).copied().unwrap_or(0)
+	}
+	pub fn chunks(&self) -> impl Iterator<Item = ((i64, i64), u64)> + '_ {
+		self.0.iter().map(|(chunk, mask)| (*chunk, *mask))
+	}
+	pub fn insert_chunk(&mut self, chunk_x: i64, chunk_y: i64, mask: u64) {
+		if mask != 0 {
+			*self.0.entry((chunk_x, chunk_y)).or_default() |= mask;
+		}
+	}
+}
+
+// Tiles within `radius` of `from` with a clear line to them. Something that blocks sight can be
+// seen itself, just not what's behind it.
+pub fn visible(from: &TilePos, radius: i64, blocks: impl Fn(&TilePos) -> bool) -> Vec<TilePos> {
+	let mut seen = vec![];
+	for x in -radius..=radius {
+		for y in -radius..=radius {
+			if x * x + y * y > radius * radius {
+				continue;
+			}
+			let to = *from + &(x, y).into();
+			if !between(from, &to).any(|pos| blocks(&pos)) {
+				seen.push(to);
+			}
+		}
+	}
+	seen
+}
+
+// Tiles on the line from one to the other, leaving out both ends
+fn between(from: &TilePos, to: &TilePos) -> impl Iterator<Item = TilePos> {
+	let (from, offset) = (*from, *to - from);
+	let steps = offset.x.abs().max(offset.y.abs());
+	(1..steps).map(move |step| {
+		// Rounded to the nearest tile, halves up
+		let along = |distance: i64| (distance * step * 2 + steps).div_euclid(steps * 2);
+		from + &(along(offset.x), along(offset.y)).into()
+	})
+}
+
+// Darkens `light` for tiles the player hasn't seen, keeping alpha
+pub fn unexplored(light: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+	light.map(|column| {
+		[
+			column[0] * UNEXPLORED_LIGHT,
+			column[1] * UNEXPLORED_LIGHT,
+			column[2] * UNEXPLORED_LIGHT,
+			column[3],
+		]
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn sees(blockers: &[(i64, i64)], to: (i64, i64)) -> bool {
+		let blocks = |pos: &TilePos| blockers.contains(&(pos.x, pos.y));
+		visible(&(0, 0).into(), VISION_RADIUS, blocks).contains(&to.into())
+	}
+
+	#[test]
+	fn blockers_hide_what_is_behind() {
+		assert!(sees(&[(2, 0)], (2, 0)));
+		assert!(!sees(&[(2, 0)], (3, 0)));
+		assert!(!sees(&[(2, 0)], (8, 0)));
+		assert!(sees(&[(2, 0)], (0, 3))); // Only behind it
+		assert!(sees(&[(1, 1)], (1, 1)));
+		assert!(!sees(&[(1, 1)], (2, 2)));
+	}
+
+	#[test]
+	fn radius_respected() {
+		assert!(sees(&[], (VISION_RADIUS, 0)));
+		assert!(sees(&[], (0, -VISION_RADIUS)));
+		assert!(!sees(&[], (VISION_RADIUS + 1, 0)));
+		assert!(!sees(&[], (6, 6))); // Within the square, outside the circle
+	}
+
+	#[test]
+	fn between_leaves_out_ends() {
+		let line = |to: (i64, i64)| {
+			between(&(0, 0).into(), &to.into())
+				.map(|pos| (pos.x, pos.y))
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(line((1, 1)), vec![]);
+		assert_eq!(line((3, 0)), vec![(1, 0), (2, 0)]);
+		assert_eq!(line((4, 2)), vec![(1, 1), (2, 1), (3, 2)]);
+		assert_eq!(line((-4, -2)), vec![(-1, 0), (-2, -1), (-3, -1)]); // Halves round up
+	}
+}
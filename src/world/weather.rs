@@ -0,0 +1,177 @@
+use {
+	super::{
+		season::Season,
+		tile::TilePos,
+	},
+	crate::{
+		animation::phase_offset,
+		atlas::TextureType,
+		frame_manager::draw::Draw,
+		tile::{
+			GroundType,
+			ResourceType,
+		},
+		CHUNK_X,
+		CHUNK_Y,
+		TILE_SIZE,
+	},
+	crow::{
+		Context,
+		WindowSurface,
+	},
+	std::{
+		collections::hash_map::DefaultHasher,
+		hash::{
+			Hash,
+			Hasher,
+		},
+	},
+};
+
+pub const REGION_CHUNKS: i64 = 4; // Regions are square, :. 16x16 tiles
+const SPELL_LEN: u64 = 1200; // Ticks of the same weather, :. 2 minutes
+const FOG_DENSITY: f32 = 0.45;
+const RAIN_LIGHT: f32 = 0.8;
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Weather {
+	Clear,
+	Rain,
+	Snow,
+	Fog,
+}
+impl Weather {
+	// Multiplies how fast plants grow back
+	pub fn growth_rate(&self) -> f32 {
+		match self {
+			Weather::Clear | Weather::Fog => 1.0,
+			Weather::Rain => 2.0,
+			Weather::Snow => 0.0,
+		}
+	}
+	// Applied after `light`, rain darkens and fog washes colours out towards grey
+	pub fn tint(&self, light: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+		match self {
+			Weather::Clear | Weather::Snow => light,
+			Weather::Rain => multiply(
+				[
+					[RAIN_LIGHT, 0.0, 0.0, 0.0],
+					[0.0, RAIN_LIGHT, 0.0, 0.0],
+					[0.0, 0.0, RAIN_LIGHT + 0.05, 0.0],
+					[0.0, 0.0, 0.0, 1.0],
+				],
+				light,
+			),
+			Weather::Fog => {
+				// Columns, so the last adds grey in proportion to alpha
+				let keep = 1.0 - FOG_DENSITY;
+				let grey = FOG_DENSITY * 0.7;
+				multiply(
+					[
+						[keep, 0.0, 0.0, 0.0],
+						[0.0, keep, 0.0, 0.0],
+						[0.0, 0.0, keep, 0.0],
+						[grey, grey, grey, 1.0],
+					],
+					light,
+				)
+			}
+		}
+	}
+	// Particles over the tile at `pos` (bottom left), moving with `frame`
+	pub fn draw_particles(
+		&self,
+		ctx: &mut Context,
+		surface: &mut WindowSurface,
+		pos: (i32, i32),
+		tile_pos: &TilePos,
+		frame: usize,
+	) {
+		let (count, fall, colour) = match self {
+			Weather::Rain => (3, 12, (0.55, 0.65, 0.95, 0.8)),
+			Weather::Snow => (2, 2, (0.95, 0.95, 1.0, 1.0)),
+			Weather::Clear | Weather::Fog => return,
+		};
+		let size = TILE_SIZE as i32;
+		let rng = fastrand::Rng::with_seed(phase_offset(tile_pos) as u64);
+		for _ in 0..count {
+			let x = pos.0 + rng.i32(0..size);
+			let y = pos.1 + (rng.i32(0..size) - (frame as i32).wrapping_mul(fall)).rem_euclid(size);
+			match self {
+				Weather::Rain => ctx.debug_line(surface, (x, y), (x + 2, y + 8), colour),
+				_ => ctx.debug_rectangle(surface, (x, y), (x + 2, y + 2), colour),
+			}
+		}
+	}
+}
+
+// Shipped overlays drawn over tiles lying under snow, where the atlas has one
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnowOverlay {
+	Ground(GroundType),
+	Resource(ResourceType),
+}
+impl Draw for SnowOverlay {
+	fn texture_type(&self) -> Option<TextureType> {
+		Some(match self {
+			SnowOverlay::Ground(ground) => TextureType::SnowyGround(ground.clone()),
+			SnowOverlay::Resource(resource) => TextureType::SnowyResource(resource.clone()),
+		})
+	}
+}
+
+pub fn region_of(pos: &TilePos) -> (i64, i64) {
+	(
+		pos.x.div_euclid(CHUNK_X * REGION_CHUNKS),
+		pos.y.div_euclid(CHUNK_Y * REGION_CHUNKS),
+	)
+}
+
+// Weather only depends on the seeds, region and time, so it's the same however the world was
+// explored and needs no saving
+#[derive(Clone, Copy, Debug)]
+pub struct WeatherMap {
+	seed: u64,
+}
+impl WeatherMap {
+	pub fn new(seed: u64) -> Self {
+		Self { seed }
+	}
+	pub fn at(&self, region: (i64, i64), tick: u64, season: Season) -> Weather {
+		self.in_spell(region, tick / SPELL_LEN, season)
+	}
+	// Snow lies for a spell after it stops, unless rain washes it away
+	pub fn snow_covered(&self, region: (i64, i64), tick: u64, season: Season) -> bool {
+		let spell = tick / SPELL_LEN;
+		match self.in_spell(region, spell, season) {
+			Weather::Snow => true,
+			Weather::Rain => false,
+			Weather::Clear | Weather::Fog => {
+				spell > 0 && self.in_spell(region, spell - 1, season) == Weather::Snow
+			}
+		}
+	}
+	fn in_spell(&self, region: (i64, i64), spell: u64, season: Season) -> Weather {
+		let mut hasher = DefaultHasher::new();
+		(self.seed, region, spell).hash(&mut hasher);
+		let mut roll = fastrand::Rng::with_seed(hasher.finish()).u32(0..20);
+		let weathers = [Weather::Clear, Weather::Rain, Weather::Snow, Weather::Fog];
+		for (weather, odds) in weathers.into_iter().zip(season.weather_odds()) {
+			if roll < odds {
+				return weather;
+			}
+			roll -= odds;
+		}
+		Weather::Clear
+	}
+}
+
+fn multiply(a: [[f32; 4]; 4], b: [[f32; 4]; 4]) -> [[f32; 4]; 4] {
+	let mut product = [[0.0; 4]; 4];
+	for (column, b_column) in product.iter_mut().zip(b.iter()) {
+		for (row, value) in column.iter_mut().enumerate() {
+			*value = (0..4).map(|k| a[k][row] * b_column[k]).sum();
+		}
+	}
+	product
+}